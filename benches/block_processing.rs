@@ -0,0 +1,68 @@
+//! Benchmark: per-sample `evaluate` vs. block-based `evaluate_block`.
+//!
+//! Run with `cargo bench`. Each case renders the same number of samples both ways and prints the
+//! throughput of each, plus the speedup of the block version.
+
+extern crate hound;
+
+// The project is a binary, so pull the dsp module in directly:
+#[path = "../src/dsp/mod.rs"]
+#[allow(dead_code, unused_imports)]
+mod dsp;
+
+use std::time::Instant;
+use dsp::traits::Signal;
+use dsp::generators::{Sine, Saw, Square, Triangle};
+use dsp::add_signals::AddSignals;
+use dsp::negate_signal::NegateSignal;
+
+// Constants:
+const SAMPLES: usize = 10_000_000;
+const BLOCK_SIZE: usize = 1024;
+
+fn main() {
+    bench("Sine", || Box::new(Sine::new(1.0, 440.0, 0.0)));
+    bench("Saw", || Box::new(Saw::new(1.0, 440.0, 0.0)));
+    bench("Square", || Box::new(Square::new(1.0, 440.0, 0.0)));
+    bench("Triangle", || Box::new(Triangle::new(1.0, 440.0, 0.0)));
+    bench("AddSignals (saw - square + triangle)", || Box::new(AddSignals::new(vec![
+        Box::new(Saw::new(1.0, 440.0, 0.0)),
+        Box::new(NegateSignal::new(Box::new(Square::new(1.0, 220.0, 0.0)))),
+        Box::new(Triangle::new(1.0, 110.0, 0.0)),
+    ])));
+}
+
+/// Time SAMPLES samples of the signal made by `make_signal`, both per-sample and per-block.
+fn bench<F: Fn() -> Box<Signal>>(name: &str, make_signal: F) {
+    let mut checksum = 0.0;
+
+    // Per-sample, through a Box<Signal> like the rest of the project uses:
+    let mut signal = make_signal();
+    let start = Instant::now();
+    for _ in 0..SAMPLES {
+        checksum += signal.evaluate();
+    }
+    let per_sample = seconds(start);
+
+    // Per-block:
+    let mut signal = make_signal();
+    let mut buffer = vec![0f64; BLOCK_SIZE];
+    let start = Instant::now();
+    for _ in 0..SAMPLES / BLOCK_SIZE {
+        signal.evaluate_block(&mut buffer);
+        checksum += buffer[0];
+    }
+    let per_block = seconds(start) * (SAMPLES as f64) / ((SAMPLES / BLOCK_SIZE * BLOCK_SIZE) as f64);
+
+    println!("{:40} per-sample: {:7.1} Msamples/s   per-block: {:7.1} Msamples/s   speedup: {:.2}x   ({})",
+             name,
+             (SAMPLES as f64) / per_sample / 1e6,
+             (SAMPLES as f64) / per_block / 1e6,
+             per_sample / per_block,
+             checksum);
+}
+
+fn seconds(start: Instant) -> f64 {
+    let elapsed = start.elapsed();
+    elapsed.as_secs() as f64 + (elapsed.subsec_nanos() as f64) * 1e-9
+}
@@ -1,20 +1,37 @@
 //! Audio module
 //!
 //! Send audio samples here, play audio through speakers.
+//!
+//! Samples arrive through a ring buffer as interleaved frames (NUM_CHANNELS samples per frame, one
+//! per channel). Ring buffers rather than channels, since the PortAudio callback mustn't block or
+//! allocate.
 
 use portaudio as pa;
-use chan;
+use ringbuf::{HeapCons, HeapProd};
+use ringbuf::traits::{Consumer, Observer, Producer};
 use std::{thread, time};
+use dsp::context::ProcessContext;
 
 // Constants:
-const NUM_CHANNELS: i32 = 2;
-const FRAMES_PER_BUFFER: u32 = 1024;
+/// Number of output channels (2 = stereo)
+pub const NUM_CHANNELS: i32 = 2;
+/// A good number of frames for PortAudio to ask for at a time (and so a good block size for
+/// generating audio)
+pub const FRAMES_PER_BUFFER: u32 = 1024;
 const BUFFER_SECONDS: f64 = 0.100;  // Buffer samples for 100ms -- reduces chances of underrun
-use audio_playground::SAMPLE_RATE;
 
 /// "Run" the audio thread
 /// Probably want to run this in a separate thread and send samples over a channel.
-pub fn run(recv_audio: chan::Receiver<f64>, send_points: chan::Sender<f64>) -> Result<(), pa::Error> {
+///
+/// PortAudio gets set up with the context's sample rate, and asks for `context.block_size` frames
+/// at a time.
+///
+/// `recv_audio` carries interleaved frames of NUM_CHANNELS samples each (if it runs dry, the
+/// output is silent until it catches up). Each frame is mixed down to mono, and the mono samples
+/// are pushed on to `send_points` (or dropped, if it's full: the grapher stops reading once it has
+/// enough).
+pub fn run(context: ProcessContext, mut recv_audio: HeapCons<f64>, mut send_points: HeapProd<f64>)
+    -> Result<(), pa::Error> {
     // Sleep a little so we don't underrun our audio buffer (probably not even needed but whatever):
     thread::sleep(time::Duration::new(0, 100_000));
 
@@ -24,7 +41,8 @@ pub fn run(recv_audio: chan::Receiver<f64>, send_points: chan::Sender<f64>) -> R
     println!("=============");
 
     // Set up our settings - set a buffer amount to try to reduce underruns:
-    let mut settings = try!(pa.default_output_stream_settings(NUM_CHANNELS, SAMPLE_RATE, FRAMES_PER_BUFFER));
+    let mut settings = try!(pa.default_output_stream_settings(NUM_CHANNELS, context.sample_rate,
+                                                              context.block_size as u32));
     settings.params.suggested_latency = BUFFER_SECONDS;
 
     // This callback function will be called by PortAudio when it needs more audio samples.
@@ -37,14 +55,31 @@ pub fn run(recv_audio: chan::Receiver<f64>, send_points: chan::Sender<f64>) -> R
     // of (NUM_CHANNELS * frames) samples into the buffer.
     // The samples are "interleaved" by default, so the structure of buffer looks like:
     // [ch0_sample0, ch1_sample0, ch0_sample1, ch1_sample1, ch0_sample2, ch1_sample2, ...]
-    let callback = move |pa::OutputStreamCallbackArgs { buffer, frames, .. }| {
-        let mut i = 0;
-        for _ in 0..frames {
-            let sample = recv_audio.recv().unwrap();
-            send_points.send(sample.clone());
-            buffer[i]   = sample as f32;
-            buffer[i+1] = sample as f32;
-            i += 2;
+    //
+    // Everything the callback needs is allocated up front: a block of samples taken from the ring
+    // buffer, and the mono mixdown of it.
+    let channels = NUM_CHANNELS as usize;
+    let mut samples = vec![0f64; context.block_size * channels];
+    let mut points = vec![0f64; context.block_size];
+    let callback = move |pa::OutputStreamCallbackArgs { buffer, .. }| {
+        for output in buffer.chunks_mut(samples.len()) {
+            // Only take whole frames, so the channels stay in step after running dry:
+            let samples = &mut samples[..output.len()];
+            let available = recv_audio.occupied_len() / channels * channels;
+            let received = recv_audio.pop_slice(&mut samples[..available.min(output.len())]);
+            for sample in samples[received..].iter_mut() {
+                *sample = 0.0;
+            }
+
+            let points = &mut points[..output.len() / channels];
+            let frames = output.chunks_mut(channels).zip(samples.chunks(channels));
+            for ((output_frame, frame), point) in frames.zip(points.iter_mut()) {
+                for (output_sample, sample) in output_frame.iter_mut().zip(frame) {
+                    *output_sample = *sample as f32;
+                }
+                *point = frame.iter().sum::<f64>() / (channels as f64);
+            }
+            send_points.push_slice(points);
         }
         pa::Continue
     };
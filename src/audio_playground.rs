@@ -2,8 +2,8 @@
 //!
 //! A driver application for the DSP module where I play sounds and graph graphs.
 
-use chan;
-use std::thread;
+use ringbuf::{HeapRb, HeapProd};
+use std::{thread, time};
 
 // Modules defined within this project:
 use dsp;
@@ -11,40 +11,56 @@ use audio;
 use graph;
 
 // Traits:
-use chan::Sender;
-use dsp::traits::Signal;
+use ringbuf::traits::{Producer, Split};
+use dsp::traits::MultichannelSignal;
 
-/// Audio playback samplerate, in Hz
+// Types:
+use dsp::window::Window;
+use dsp::context::ProcessContext;
+
+/// Audio playback samplerate, in Hz (only used to build the ProcessContext that the signal graph,
+/// the audio thread and the grapher all run with)
 pub const SAMPLE_RATE: f64 = 44100.0;
 
+/// Window function the grapher applies before taking the DFT
+pub const GRAPH_WINDOW: Window = Window::Hann;
+
+/// Time between the starts of consecutive notes, in seconds
+pub const NOTE_SECONDS: f64 = 1.0;
+
 /// Main driver function for the "audio playground"
 pub fn audio_playground() {
     // The general signal flow for our program is currently:
     // (audio processing) --> (audio playing) --> (grapher - first X samples)
     //
-    // Each component runs in its own thread, and the arrows represent channels that the threads
-    // send data over.
+    // Each component runs in its own thread, and the arrows represent (lock-free) ring buffers
+    // that the threads pass samples through.
 
-    // Create the channels for communication:
-    let (send_audio, recv_audio) = chan::sync(SAMPLE_RATE as usize);
-    let (send_graph_points, recv_graph_points) = chan::sync(SAMPLE_RATE as usize);
+    // Everything runs with the same settings:
+    let context = ProcessContext::new(SAMPLE_RATE, audio::FRAMES_PER_BUFFER as usize);
+
+    // Create the ring buffers, each holding about a second's worth of samples:
+    let one_second = context.sample_rate as usize;
+    let (send_audio, recv_audio) =
+        HeapRb::<f64>::new(one_second * audio::NUM_CHANNELS as usize).split();
+    let (send_graph_points, recv_graph_points) = HeapRb::<f64>::new(one_second).split();
 
     // Collect all our threads so we can .join() later:
     let mut children = vec![];
 
     // Create the audio processing thread:
     children.push(thread::spawn(move || {
-        generate_audio(send_audio);
+        generate_audio(context, send_audio);
     }));
 
     // Create the audio playing thread:
     children.push(thread::spawn(move || {
-        audio::run(recv_audio, send_graph_points).unwrap()
+        audio::run(context, recv_audio, send_graph_points).unwrap()
     }));
 
     // Create the grapher thread:
     children.push(thread::spawn(move || {
-        graph::run(recv_graph_points);
+        graph::run(context, recv_graph_points, GRAPH_WINDOW);
     }));
 
     // Wait for all the child threads to finish:
@@ -53,10 +69,36 @@ pub fn audio_playground() {
     }
 }
 
-fn generate_audio(send_audio: Sender<f64>) {
-    let mut some_generator = dsp::generators::Triangle::new(0.1, 440.0, 0.0);
+fn generate_audio(context: ProcessContext, mut send_audio: HeapProd<f64>) {
+    // Play one note per NOTE_SECONDS, held for the first half of it:
+    let mut envelope = dsp::envelopes::Envelope::adsr(0.01, 0.2, 0.6, 0.3);
+    envelope.set_range(0.0, 0.1);
+    let gate = envelope.gate();
+    let some_generator = dsp::generators::Triangle::new(envelope, 440.0, 0.0);
+    let mut output = dsp::channels::Duplicate::new(Box::new(some_generator),
+                                                   audio::NUM_CHANNELS as usize);
+    output.prepare(&context);
 
+    // Generate whole blocks of interleaved frames at a time:
+    let mut block = vec![0f64; context.block_size * output.channels()];
+    let note_blocks = (NOTE_SECONDS * context.sample_rate / context.block_size as f64) as usize;
+    let mut block_number = 0;
     loop {
-        send_audio.send(some_generator.evaluate());
+        if block_number % note_blocks == 0 {
+            gate.on();
+        }
+        else if block_number % note_blocks == note_blocks / 2 {
+            gate.off();
+        }
+        block_number += 1;
+
+        output.evaluate_frames(&mut block);
+
+        // Wait for the audio thread to make room for the rest whenever the ring buffer fills up:
+        let mut sent = send_audio.push_slice(&block);
+        while sent < block.len() {
+            thread::sleep(time::Duration::from_millis(5));
+            sent += send_audio.push_slice(&block[sent..]);
+        }
     }
 }
\ No newline at end of file
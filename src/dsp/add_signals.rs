@@ -1,20 +1,31 @@
 //! Signal that takes multiple other signals and adds them together
 
 use dsp::traits::Signal;
+use dsp::context::ProcessContext;
 
 /// AddSignals struct
 pub struct AddSignals {
     added: Vec<Box<Signal>>,
+    scratch: Vec<f64>,  // Block buffer for evaluate_block
 }
 
 impl AddSignals {
     /// Creates a new AddSignals signal
     pub fn new(added: Vec<Box<Signal>>) -> AddSignals {
-        AddSignals {added}
+        AddSignals {added, scratch: vec![]}
     }
 }
 
+signal_ops!(AddSignals);
+
 impl Signal for AddSignals {
+    fn prepare(&mut self, context: &ProcessContext) {
+        for c in &mut self.added {
+            c.prepare(context);
+        }
+        self.scratch = vec![0f64; context.block_size];
+    }
+
     fn evaluate(&mut self) -> f64 {
         let mut output: f64 = 0.0;
 
@@ -24,4 +35,22 @@ impl Signal for AddSignals {
 
         output
     }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        for sample in buffer.iter_mut() {
+            *sample = 0.0;
+        }
+
+        // Evaluate each signal into the scratch buffer, and accumulate them into the output:
+        if self.scratch.len() < buffer.len() {
+            self.scratch.resize(buffer.len(), 0.0);
+        }
+        let scratch = &mut self.scratch[..buffer.len()];
+        for c in &mut self.added {
+            c.evaluate_block(scratch);
+            for (sample, added) in buffer.iter_mut().zip(scratch.iter()) {
+                *sample += *added;
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,31 @@
+//! Signal that mixes all the channels of a multi-channel signal down to mono
+
+use dsp::traits::{Signal, MultichannelSignal};
+use dsp::context::ProcessContext;
+
+/// Downmix struct
+pub struct Downmix {
+    downmixed: Box<MultichannelSignal>,
+    frame: Vec<f64>,
+}
+
+impl Downmix {
+    /// Creates a new Downmix signal, which outputs the average of every channel of `downmixed`
+    pub fn new(downmixed: Box<MultichannelSignal>) -> Downmix {
+        let frame = vec![0f64; downmixed.channels()];
+        Downmix {downmixed, frame}
+    }
+}
+
+signal_ops!(Downmix);
+
+impl Signal for Downmix {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.downmixed.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        self.downmixed.evaluate_frame(&mut self.frame);
+        self.frame.iter().sum::<f64>() / (self.frame.len() as f64)
+    }
+}
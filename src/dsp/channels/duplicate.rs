@@ -0,0 +1,50 @@
+//! Multi-channel signal that plays the same mono signal on every channel
+
+use dsp::traits::{Signal, MultichannelSignal};
+use dsp::context::ProcessContext;
+
+/// Duplicate struct
+pub struct Duplicate {
+    duplicated: Box<Signal>,
+    channels: usize,
+    scratch: Vec<f64>,  // Mono block buffer for evaluate_frames
+}
+
+impl Duplicate {
+    /// Creates a new Duplicate signal with `channels` copies of `duplicated`
+    pub fn new(duplicated: Box<Signal>, channels: usize) -> Duplicate {
+        Duplicate {duplicated, channels, scratch: vec![]}
+    }
+}
+
+impl MultichannelSignal for Duplicate {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.duplicated.prepare(context);
+        self.scratch = vec![0f64; context.block_size];
+    }
+
+    fn channels(&self) -> usize {
+        self.channels
+    }
+
+    fn evaluate_frame(&mut self, frame: &mut [f64]) {
+        let sample = self.duplicated.evaluate();
+        for s in frame.iter_mut() {
+            *s = sample;
+        }
+    }
+
+    fn evaluate_frames(&mut self, buffer: &mut [f64]) {
+        // Evaluate a whole mono block at once, then copy it into every channel:
+        let frames = buffer.len() / self.channels;
+        if self.scratch.len() < frames {
+            self.scratch.resize(frames, 0.0);
+        }
+        self.duplicated.evaluate_block(&mut self.scratch[..frames]);
+        for (frame, sample) in buffer.chunks_mut(self.channels).zip(self.scratch.iter()) {
+            for s in frame.iter_mut() {
+                *s = *sample;
+            }
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! Multi-channel signal built out of one mono signal per channel
+
+use dsp::traits::{Signal, MultichannelSignal};
+use dsp::context::ProcessContext;
+
+/// Merge struct
+pub struct Merge {
+    merged: Vec<Box<Signal>>,
+}
+
+impl Merge {
+    /// Creates a new Merge signal; `merged[i]` becomes channel i
+    pub fn new(merged: Vec<Box<Signal>>) -> Merge {
+        Merge {merged}
+    }
+}
+
+impl MultichannelSignal for Merge {
+    fn prepare(&mut self, context: &ProcessContext) {
+        for c in &mut self.merged {
+            c.prepare(context);
+        }
+    }
+
+    fn channels(&self) -> usize {
+        self.merged.len()
+    }
+
+    fn evaluate_frame(&mut self, frame: &mut [f64]) {
+        for (s, c) in frame.iter_mut().zip(self.merged.iter_mut()) {
+            *s = c.evaluate();
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Conversions between left/right and mid/side stereo
+//!
+//! Mid/side represents a stereo signal as the part both channels have in common (mid = (L+R)/2)
+//! and the part that differs between them (side = (L-R)/2). Processing the two separately is handy
+//! for things like widening or narrowing the stereo image. The decoder is the exact inverse of the
+//! encoder: L = mid + side, R = mid - side.
+
+use dsp::traits::MultichannelSignal;
+use dsp::context::ProcessContext;
+
+/// MidSideEncode struct: turns a left/right stereo signal into a mid/side one
+pub struct MidSideEncode {
+    encoded: Box<MultichannelSignal>,
+}
+
+impl MidSideEncode {
+    /// Creates a new MidSideEncode signal (`encoded` must be stereo)
+    pub fn new(encoded: Box<MultichannelSignal>) -> MidSideEncode {
+        assert!(encoded.channels() == 2);
+        MidSideEncode {encoded}
+    }
+}
+
+impl MultichannelSignal for MidSideEncode {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.encoded.prepare(context);
+    }
+
+    fn channels(&self) -> usize {
+        2
+    }
+
+    fn evaluate_frame(&mut self, frame: &mut [f64]) {
+        self.encoded.evaluate_frame(frame);
+        let (left, right) = (frame[0], frame[1]);
+        frame[0] = (left + right) / 2.0;
+        frame[1] = (left - right) / 2.0;
+    }
+}
+
+/// MidSideDecode struct: turns a mid/side stereo signal into a left/right one
+pub struct MidSideDecode {
+    decoded: Box<MultichannelSignal>,
+}
+
+impl MidSideDecode {
+    /// Creates a new MidSideDecode signal (`decoded` must have mid on channel 0 and side on
+    /// channel 1, e.g. a `Merge` of two mono signals)
+    pub fn new(decoded: Box<MultichannelSignal>) -> MidSideDecode {
+        assert!(decoded.channels() == 2);
+        MidSideDecode {decoded}
+    }
+}
+
+impl MultichannelSignal for MidSideDecode {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.decoded.prepare(context);
+    }
+
+    fn channels(&self) -> usize {
+        2
+    }
+
+    fn evaluate_frame(&mut self, frame: &mut [f64]) {
+        self.decoded.evaluate_frame(frame);
+        let (mid, side) = (frame[0], frame[1]);
+        frame[0] = mid + side;
+        frame[1] = mid - side;
+    }
+}
@@ -0,0 +1,102 @@
+//! Conversions between mono and multi-channel signals.
+//!
+//! `Signal`s are always mono, and `MultichannelSignal`s produce a frame of samples (one per
+//! channel) at a time. The adapters in here turn one into the other.
+
+// Mono -> N channels, same signal on every channel
+pub mod duplicate;
+pub use self::duplicate::Duplicate;
+
+// Mono -> stereo, with a pan position
+pub mod pan;
+pub use self::pan::Pan;
+
+// N mono signals -> N channels
+pub mod merge;
+pub use self::merge::Merge;
+
+// Stereo <-> mid/side
+pub mod mid_side;
+pub use self::mid_side::{MidSideEncode, MidSideDecode};
+
+// N channels -> mono
+pub mod downmix;
+pub use self::downmix::Downmix;
+
+#[cfg(test)]
+mod tests {
+    use std::f64;
+    use dsp::channels::*;
+    use dsp::traits::{Signal, MultichannelSignal};
+    use dsp::context::ProcessContext;
+    use dsp::generators::White;
+    use dsp::ops::SignalExt;
+
+    /// A constant 1.0 (noise with no amplitude).
+    fn one() -> Box<Signal> {
+        White::new(0.0, 1.0, 0).boxed()
+    }
+
+    fn noise(seed: u64) -> Box<Signal> {
+        White::new(1.0, 0.0, seed).boxed()
+    }
+
+    #[test]
+    fn pan_is_equal_power() {
+        let half = f64::consts::FRAC_1_SQRT_2;
+        for &(position, left, right) in &[(-1.0, 1.0, 0.0), (0.0, half, half), (1.0, 0.0, 1.0),
+                                          (2.0, 0.0, 1.0)] {
+            let mut pan = Pan::new(one(), position);
+            let mut frame = [0f64; 2];
+            pan.evaluate_frame(&mut frame);
+            assert!((frame[0] - left).abs() < 1e-12 && (frame[1] - right).abs() < 1e-12);
+            assert!((frame[0] * frame[0] + frame[1] * frame[1] - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn mid_side_round_trip() {
+        let mut left = White::new(1.0, 0.0, 1);
+        let mut right = White::new(1.0, 0.0, 2);
+        let stereo = Merge::new(vec![noise(1), noise(2)]);
+        let mut encoded = MidSideEncode::new(Box::new(Merge::new(vec![noise(1), noise(2)])));
+        let mut round_trip = MidSideDecode::new(Box::new(MidSideEncode::new(Box::new(stereo))));
+
+        let (mut mid_side, mut frame) = ([0f64; 2], [0f64; 2]);
+        for _ in 0..100 {
+            let (l, r) = (left.evaluate(), right.evaluate());
+            encoded.evaluate_frame(&mut mid_side);
+            assert!((mid_side[0] - (l + r) / 2.0).abs() < 1e-12);
+            assert!((mid_side[1] - (l - r) / 2.0).abs() < 1e-12);
+            round_trip.evaluate_frame(&mut frame);
+            assert!((frame[0] - l).abs() < 1e-12 && (frame[1] - r).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn downmix_averages_the_channels() {
+        let mut sources: Vec<White> = (1..4).map(|seed| White::new(1.0, 0.0, seed)).collect();
+        let mut downmix = Downmix::new(Box::new(Merge::new(vec![noise(1), noise(2), noise(3)])));
+        for _ in 0..100 {
+            let expected = sources.iter_mut().map(|s| s.evaluate()).sum::<f64>() / 3.0;
+            assert!((downmix.evaluate() - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn duplicate_frames_match_single_frames() {
+        let mut block = Duplicate::new(noise(1), 3);
+        let mut single = Duplicate::new(noise(1), 3);
+        block.prepare(&ProcessContext::new(44100.0, 16));
+        single.prepare(&ProcessContext::new(44100.0, 16));
+
+        let mut buffer = vec![0f64; 3 * 16];
+        block.evaluate_frames(&mut buffer);
+        let mut frame = [0f64; 3];
+        for expected in buffer.chunks(3) {
+            single.evaluate_frame(&mut frame);
+            assert_eq!(&frame[..], expected);
+            assert!(frame[0] == frame[1] && frame[1] == frame[2]);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Stereo signal that places a mono signal somewhere between the left and right channels
+//!
+//! This uses the equal-power (sine/cosine) pan law, so the overall loudness stays the same as the
+//! signal moves across the stereo field: a centered signal comes out of each channel at
+//! 1/sqrt(2) (-3dB), instead of 0.5 (-6dB) like a linear crossfade would give.
+
+use dsp::traits::{Signal, MultichannelSignal};
+use dsp::context::ProcessContext;
+use std::f64;
+
+/// Pan struct
+pub struct Pan {
+    panned: Box<Signal>,
+    left_gain: f64,
+    right_gain: f64,
+}
+
+impl Pan {
+    /// Creates a new Pan signal.
+    ///
+    /// `position` goes from -1.0 (hard left) through 0.0 (center) to 1.0 (hard right).
+    pub fn new(panned: Box<Signal>, position: f64) -> Pan {
+        let mut pan = Pan {panned, left_gain: 0.0, right_gain: 0.0};
+        pan.set_position(position);
+        pan
+    }
+
+    /// Moves the signal to a new pan position (-1.0 to 1.0).
+    pub fn set_position(&mut self, position: f64) {
+        let angle = (position.clamp(-1.0, 1.0) + 1.0) * f64::consts::PI / 4.0;
+        self.left_gain = angle.cos();
+        self.right_gain = angle.sin();
+    }
+}
+
+impl MultichannelSignal for Pan {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.panned.prepare(context);
+    }
+
+    fn channels(&self) -> usize {
+        2
+    }
+
+    fn evaluate_frame(&mut self, frame: &mut [f64]) {
+        let sample = self.panned.evaluate();
+        frame[0] = sample * self.left_gain;
+        frame[1] = sample * self.right_gain;
+    }
+}
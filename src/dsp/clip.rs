@@ -0,0 +1,38 @@
+//! Signal that hard-clips another signal to a range
+
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+
+/// Clip struct
+pub struct Clip {
+    clipped: Box<Signal>,
+    low: f64,   // Lowest value let through
+    high: f64,  // Highest value let through
+}
+
+impl Clip {
+    /// Creates a new Clip signal, which limits the signal to the range `low` to `high`
+    pub fn new(clipped: Box<Signal>, low: f64, high: f64) -> Clip {
+        assert!(low <= high, "The low end of the clipping range can't be above the high end");
+        Clip {clipped, low, high}
+    }
+}
+
+signal_ops!(Clip);
+
+impl Signal for Clip {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.clipped.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        self.clipped.evaluate().max(self.low).min(self.high)
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        self.clipped.evaluate_block(buffer);
+        for sample in buffer.iter_mut() {
+            *sample = sample.max(self.low).min(self.high);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! Processing context: the settings a whole signal graph runs with.
+//!
+//! Signals don't know what sample rate they're going to be played back at when they're created,
+//! so every signal starts out assuming DEFAULT_SAMPLE_RATE. Before running a signal graph, call
+//! `prepare` on its output signal with the real settings; every signal passes the context on to
+//! the signals it's built from, so the whole graph ends up using the same settings.
+
+/// Sample rate every signal assumes until it gets prepared with a different one, in Hz
+pub const DEFAULT_SAMPLE_RATE: f64 = 44100.0;
+
+/// Block size every signal assumes until it gets prepared with a different one, in samples
+pub const DEFAULT_BLOCK_SIZE: usize = 1024;
+
+/// ProcessContext struct
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProcessContext {
+    /// Sample rate the signals are evaluated at, in Hz
+    pub sample_rate: f64,
+    /// Largest number of samples that will be requested from `evaluate_block` at once
+    pub block_size: usize,
+}
+
+impl ProcessContext {
+    /// Creates a new ProcessContext
+    pub fn new(sample_rate: f64, block_size: usize) -> ProcessContext {
+        ProcessContext {sample_rate, block_size}
+    }
+}
+
+impl Default for ProcessContext {
+    fn default() -> ProcessContext {
+        ProcessContext::new(DEFAULT_SAMPLE_RATE, DEFAULT_BLOCK_SIZE)
+    }
+}
@@ -0,0 +1,306 @@
+//! Fast (FFT) convolution.
+//!
+//! Convolving with a long impulse response one sample at a time (like `filters::Fir` does) costs
+//! one multiply per tap per sample, which is hopeless for a reverb's impulse response (several
+//! seconds, i.e. hundreds of thousands of taps). Convolution in the time domain is multiplication
+//! in the frequency domain though, so with an FFT the cost drops to a few multiplies per sample.
+//!
+//! The input gets processed in blocks, with one of three engines:
+//!
+//!  - `OverlapAdd`: each input block is convolved on its own, and the overlapping tails of
+//!    consecutive results are added together
+//!  - `OverlapSave`: each FFT covers the new block plus enough of the previous input that the
+//!    wrapped-around part of the (circular) convolution can simply be thrown away
+//!  - `Partitioned`: the impulse response is split into block-sized pieces that each get their
+//!    own FFT, so the FFTs stay small (and the latency stays low) however long the impulse
+//!    response is
+//!
+//! `Convolver` wraps any of them up as a signal. Since a block has to be complete before it can be
+//! transformed, the output is delayed by one block (see `Convolver::latency`).
+
+use std::path::Path;
+use hound;
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+use dsp::dft::{Complex, FftPlan};
+use dsp::wav::WavFile;
+
+// Engines
+pub mod overlap_add;
+pub use self::overlap_add::OverlapAdd;
+pub mod overlap_save;
+pub use self::overlap_save::OverlapSave;
+pub mod partitioned;
+pub use self::partitioned::Partitioned;
+
+/// Which engine a `Convolver` uses, and its block size (in samples).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Method {
+    /// Overlap-add, with one FFT big enough for a block plus the whole impulse response
+    OverlapAdd(usize),
+    /// Overlap-save, with one FFT big enough for a block plus the whole impulse response
+    OverlapSave(usize),
+    /// Uniformly partitioned convolution, with FFTs twice the block size
+    Partitioned(usize),
+}
+
+/// Convolves two whole signals in one go. The result is `a.len() + b.len() - 1` samples long.
+pub fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+    let length = a.len() + b.len() - 1;
+    let plan = FftPlan::new(length.next_power_of_two());
+
+    let mut product = transform(&plan, a);
+    for (x, y) in product.iter_mut().zip(transform(&plan, b)) {
+        *x = *x * y;
+    }
+    inverse_transform(&plan, &mut product);
+    product.iter().take(length).map(|x| x.re).collect()
+}
+
+/// FFT of `samples`, zero-padded to the length of the plan.
+fn transform(plan: &FftPlan, samples: &[f64]) -> Vec<Complex> {
+    let mut buffer = vec![Complex::new(0.0, 0.0); plan.len()];
+    transform_into(plan, samples, &mut buffer);
+    buffer
+}
+
+/// FFT of `samples`, zero-padded to the length of the plan, into `buffer` (which has to be that
+/// long already).
+fn transform_into(plan: &FftPlan, samples: &[f64], buffer: &mut [Complex]) {
+    for x in buffer.iter_mut() {
+        *x = Complex::new(0.0, 0.0);
+    }
+    for (x, &sample) in buffer.iter_mut().zip(samples) {
+        *x = Complex::new(sample, 0.0);
+    }
+    plan.process(buffer);
+}
+
+/// Inverse FFT of a spectrum, in place, with the 1/N scaling. Only the real parts of the result
+/// mean anything.
+fn inverse_transform(plan: &FftPlan, spectrum: &mut [Complex]) {
+    plan.process_inverse(spectrum);
+    let scale = 1.0 / plan.len() as f64;
+    for x in spectrum.iter_mut() {
+        *x = x.scale(scale);
+    }
+}
+
+/// One of the engines.
+enum Engine {
+    OverlapAdd(OverlapAdd),
+    OverlapSave(OverlapSave),
+    Partitioned(Partitioned),
+}
+
+impl Engine {
+    /// Creates the engine for a method, and returns it with its block size.
+    fn new(impulse_response: &[f64], method: Method) -> (Engine, usize) {
+        match method {
+            Method::OverlapAdd(size) => (Engine::OverlapAdd(OverlapAdd::new(impulse_response, size)), size),
+            Method::OverlapSave(size) => (Engine::OverlapSave(OverlapSave::new(impulse_response, size)), size),
+            Method::Partitioned(size) => (Engine::Partitioned(Partitioned::new(impulse_response, size)), size),
+        }
+    }
+
+    fn process_block(&mut self, input: &[f64], output: &mut [f64]) {
+        match *self {
+            Engine::OverlapAdd(ref mut engine) => engine.process_block(input, output),
+            Engine::OverlapSave(ref mut engine) => engine.process_block(input, output),
+            Engine::Partitioned(ref mut engine) => engine.process_block(input, output),
+        }
+    }
+}
+
+/// Convolver struct.
+pub struct Convolver {
+    filtered: Box<Signal>,  // Input signal
+    engine: Engine,
+    input: Vec<f64>,        // Input block being collected
+    output: Vec<f64>,       // Output block being played back
+    position: usize,        // Position in both blocks
+}
+
+impl Convolver {
+    /// Creates a new Convolver that convolves `filtered` with `impulse_response`.
+    pub fn new(filtered: Box<Signal>, impulse_response: Vec<f64>, method: Method) -> Convolver {
+        let (engine, block_size) = Engine::new(&impulse_response, method);
+        Convolver {
+            filtered,
+            engine,
+            input: vec![0f64; block_size],
+            output: vec![0f64; block_size],
+            position: 0,
+        }
+    }
+
+    /// Creates a new Convolver with an impulse response read from a WAV file (all its channels
+    /// averaged together).
+    ///
+    /// The impulse response isn't resampled, so it sounds right when the file's sample rate
+    /// matches the one the signal gets prepared with.
+    pub fn from_wav<P: AsRef<Path>>(filtered: Box<Signal>, path: P, method: Method)
+        -> Result<Convolver, hound::Error> {
+        let impulse_response = try!(WavFile::open(path)).mono();
+        if impulse_response.is_empty() {
+            return Err(hound::Error::FormatError("file has no samples"));
+        }
+        Ok(Convolver::new(filtered, impulse_response, method))
+    }
+
+    /// How many samples the output lags behind the input (one block).
+    pub fn latency(&self) -> usize {
+        self.input.len()
+    }
+
+    /// Convolves one sample.
+    fn process(&mut self, input: f64) -> f64 {
+        self.input[self.position] = input;
+        let output = self.output[self.position];
+        self.position += 1;
+        if self.position == self.input.len() {
+            self.engine.process_block(&self.input, &mut self.output);
+            self.position = 0;
+        }
+        output
+    }
+}
+
+signal_ops!(Convolver);
+
+impl Signal for Convolver {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.filtered.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let input = self.filtered.evaluate();
+        self.process(input)
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        self.filtered.evaluate_block(buffer);
+        for sample in buffer.iter_mut() {
+            *sample = self.process(*sample);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dsp::generators::White;
+    use dsp::wav::tests::write_temp;
+
+    fn noise(length: usize, seed: u64) -> Vec<f64> {
+        let mut noise = White::new(1.0, 0.0, seed);
+        (0..length).map(|_| noise.evaluate()).collect()
+    }
+
+    /// Runs `input` through an engine one block at a time (zero-padding the last block).
+    fn run_engine(impulse_response: &[f64], method: Method, input: &[f64]) -> Vec<f64> {
+        let (mut engine, block_size) = Engine::new(impulse_response, method);
+        let mut output = vec![];
+        let mut block = vec![0f64; block_size];
+        for chunk in input.chunks(block_size) {
+            let mut padded = chunk.to_vec();
+            padded.resize(block_size, 0.0);
+            engine.process_block(&padded, &mut block);
+            output.extend_from_slice(&block);
+        }
+        output
+    }
+
+    #[test]
+    fn convolve_matches_the_direct_sum() {
+        let (a, b) = (noise(100, 1), noise(37, 2));
+        let result = convolve(&a, &b);
+        assert_eq!(result.len(), 136);
+        for (n, y) in result.iter().enumerate() {
+            let expected: f64 = (0..b.len())
+                .filter(|&k| k <= n && n - k < a.len())
+                .map(|k| a[n - k] * b[k])
+                .sum();
+            assert!((y - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn engines_match_convolve() {
+        let input = noise(1000, 1);
+        // Shorter than, the same as and longer than the blocks
+        for &ir_length in &[1, 5, 64, 300] {
+            let impulse_response = noise(ir_length, 2);
+            let expected = convolve(&input, &impulse_response);
+            for &block_size in &[1, 7, 64, 100] {
+                let methods = [
+                    Method::OverlapAdd(block_size),
+                    Method::OverlapSave(block_size),
+                    Method::Partitioned(block_size),
+                ];
+                for &method in &methods {
+                    let output = run_engine(&impulse_response, method, &input);
+                    for (y, e) in output.iter().zip(&expected) {
+                        assert!((y - e).abs() < 1e-9, "{:?} with a {} sample impulse response",
+                                method, ir_length);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks that `convolver` (filtering `White::new(1.0, 0.0, 1)`) outputs `convolve` of its
+    /// input with `impulse_response`, `latency()` samples late.
+    fn assert_convolves(mut convolver: Convolver, impulse_response: &[f64]) {
+        let latency = convolver.latency();
+        let expected = convolve(&noise(1000, 1), impulse_response);
+        let mut output = vec![0f64; 1000 + latency];
+        // (Odd-sized blocks, so they don't line up with the convolver's own blocks)
+        for chunk in output.chunks_mut(77) {
+            convolver.evaluate_block(chunk);
+        }
+        assert!(output[..latency].iter().all(|&y| y == 0.0));
+        for (y, e) in output[latency..].iter().zip(&expected) {
+            assert!((y - e).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn convolver_matches_convolve_after_its_latency() {
+        let impulse_response = noise(300, 2);
+        let methods = [
+            (Method::OverlapAdd(64), 64),
+            (Method::OverlapSave(100), 100),
+            (Method::Partitioned(7), 7),
+        ];
+        for &(method, block_size) in &methods {
+            let input = Box::new(White::new(1.0, 0.0, 1));
+            let convolver = Convolver::new(input, impulse_response.clone(), method);
+            assert_eq!(convolver.latency(), block_size);
+            assert_convolves(convolver, &impulse_response);
+        }
+    }
+
+    #[test]
+    fn loads_impulse_responses_from_wav() {
+        // A stereo file gets averaged down to mono
+        let left = noise(300, 2);
+        let right = noise(300, 3);
+        let path = write_temp("convolver_loads_impulse_responses_from_wav.wav", 44100,
+                              &[left.clone(), right.clone()]);
+        let impulse_response: Vec<f64> = left.iter().zip(&right)
+            .map(|(&l, &r)| (l as f32 as f64 + r as f32 as f64) / 2.0)
+            .collect();
+
+        let input = Box::new(White::new(1.0, 0.0, 1));
+        let convolver = Convolver::from_wav(input, &path, Method::Partitioned(64)).unwrap();
+        assert_convolves(convolver, &impulse_response);
+
+        let empty = write_temp("convolver_empty.wav", 44100, &[vec![]]);
+        let input = Box::new(White::new(1.0, 0.0, 1));
+        assert!(Convolver::from_wav(input, &empty, Method::Partitioned(64)).is_err());
+    }
+}
@@ -0,0 +1,55 @@
+//! Overlap-add convolution.
+//!
+//! Each block of input is zero-padded, convolved with the whole impulse response in one FFT, and
+//! the result (a block plus the impulse response's length, minus one) is added into an
+//! accumulator: the first block's worth is finished and gets output, and the rest is the tail that
+//! the next few blocks add onto.
+
+use dsp::dft::{Complex, FftPlan};
+use super::{transform, transform_into, inverse_transform};
+
+/// Overlap-add convolution engine.
+pub struct OverlapAdd {
+    block_size: usize,
+    plan: FftPlan,          // FFT big enough for a block convolved with the impulse response
+    kernel: Vec<Complex>,   // FFT of the impulse response
+    tail: Vec<f64>,         // Results of earlier blocks that haven't been output yet
+    spectrum: Vec<Complex>, // Scratch space for the current block's spectrum
+}
+
+impl OverlapAdd {
+    /// Creates a new overlap-add engine for blocks of `block_size` samples.
+    pub fn new(impulse_response: &[f64], block_size: usize) -> OverlapAdd {
+        assert!(block_size > 0);
+        assert!(!impulse_response.is_empty());
+        let plan = FftPlan::new((block_size + impulse_response.len() - 1).next_power_of_two());
+        let kernel = transform(&plan, impulse_response);
+        let tail = vec![0f64; plan.len()];
+        let spectrum = vec![Complex::new(0.0, 0.0); plan.len()];
+        OverlapAdd { block_size, plan, kernel, tail, spectrum }
+    }
+
+    /// Convolves the next block of input (`block_size` samples) into `output`.
+    pub fn process_block(&mut self, input: &[f64], output: &mut [f64]) {
+        assert!(input.len() == self.block_size && output.len() == self.block_size);
+
+        transform_into(&self.plan, input, &mut self.spectrum);
+        for (x, h) in self.spectrum.iter_mut().zip(&self.kernel) {
+            *x = *x * *h;
+        }
+        inverse_transform(&self.plan, &mut self.spectrum);
+        for (t, r) in self.tail.iter_mut().zip(&self.spectrum) {
+            *t += r.re;
+        }
+
+        // Output the finished part, and shift the rest along:
+        output.copy_from_slice(&self.tail[..self.block_size]);
+        let length = self.tail.len();
+        for i in 0..length - self.block_size {
+            self.tail[i] = self.tail[i + self.block_size];
+        }
+        for t in self.tail[length - self.block_size..].iter_mut() {
+            *t = 0.0;
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Overlap-save convolution.
+//!
+//! Multiplying spectra gives a *circular* convolution, where the end of the result wraps around
+//! onto the start. Overlap-save transforms the new block together with enough of the previous
+//! input to fill the FFT; only the first (impulse response length - 1) samples of the result get
+//! wrapped onto, so the last block's worth is the correct (linear) convolution and the rest is
+//! thrown away. No tails need adding up, unlike overlap-add.
+
+use dsp::dft::{Complex, FftPlan};
+use super::{transform, transform_into, inverse_transform};
+
+/// Overlap-save convolution engine.
+pub struct OverlapSave {
+    block_size: usize,
+    plan: FftPlan,          // FFT big enough for a block plus the impulse response
+    kernel: Vec<Complex>,   // FFT of the impulse response
+    history: Vec<f64>,      // The last plan.len() input samples
+    spectrum: Vec<Complex>, // Scratch space for the history's spectrum
+}
+
+impl OverlapSave {
+    /// Creates a new overlap-save engine for blocks of `block_size` samples.
+    pub fn new(impulse_response: &[f64], block_size: usize) -> OverlapSave {
+        assert!(block_size > 0);
+        assert!(!impulse_response.is_empty());
+        let plan = FftPlan::new((block_size + impulse_response.len() - 1).next_power_of_two());
+        let kernel = transform(&plan, impulse_response);
+        let history = vec![0f64; plan.len()];
+        let spectrum = vec![Complex::new(0.0, 0.0); plan.len()];
+        OverlapSave { block_size, plan, kernel, history, spectrum }
+    }
+
+    /// Convolves the next block of input (`block_size` samples) into `output`.
+    pub fn process_block(&mut self, input: &[f64], output: &mut [f64]) {
+        assert!(input.len() == self.block_size && output.len() == self.block_size);
+
+        // Slide the new block in at the end of the history:
+        let length = self.history.len();
+        for i in 0..length - self.block_size {
+            self.history[i] = self.history[i + self.block_size];
+        }
+        self.history[length - self.block_size..].copy_from_slice(input);
+
+        transform_into(&self.plan, &self.history, &mut self.spectrum);
+        for (x, h) in self.spectrum.iter_mut().zip(&self.kernel) {
+            *x = *x * *h;
+        }
+        inverse_transform(&self.plan, &mut self.spectrum);
+        for (y, x) in output.iter_mut().zip(&self.spectrum[length - self.block_size..]) {
+            *y = x.re;
+        }
+    }
+}
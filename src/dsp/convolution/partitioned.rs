@@ -0,0 +1,84 @@
+//! Uniformly partitioned convolution.
+//!
+//! Overlap-add and overlap-save need an FFT longer than the whole impulse response, and a block
+//! size to match if they're going to be efficient, which means a lot of latency for a long
+//! reverb. Here the impulse response is cut into pieces ("partitions") the size of a block
+//! instead, each with its own FFT (twice the block size). Every block of input is transformed
+//! once, and its spectrum is kept in a "frequency-domain delay line": the output is the sum of
+//! the newest input spectrum times the first partition, the previous one times the second
+//! partition, and so on. Each sum is turned back into samples with overlap-save.
+//!
+//! The latency is just one block, whatever the length of the impulse response.
+
+use dsp::dft::{Complex, FftPlan};
+use super::{transform, transform_into, inverse_transform};
+
+/// Uniformly partitioned convolution engine.
+pub struct Partitioned {
+    block_size: usize,
+    plan: FftPlan,                  // FFT twice the block size
+    partitions: Vec<Vec<Complex>>,  // FFT of each block-sized piece of the impulse response
+    spectra: Vec<Vec<Complex>>,     // Spectra of the most recent inputs (frequency-domain delay line)
+    newest: usize,                  // Index of the newest spectrum in `spectra`
+    history: Vec<f64>,              // The last two blocks of input
+    sum: Vec<Complex>,              // Scratch space for the summed spectrum
+}
+
+impl Partitioned {
+    /// Creates a new partitioned convolution engine for blocks of `block_size` samples.
+    pub fn new(impulse_response: &[f64], block_size: usize) -> Partitioned {
+        assert!(block_size > 0);
+        assert!(!impulse_response.is_empty());
+        let plan = FftPlan::new(2 * block_size);
+        let partitions: Vec<Vec<Complex>> = impulse_response.chunks(block_size)
+            .map(|partition| transform(&plan, partition))
+            .collect();
+        let spectra = vec![vec![Complex::new(0.0, 0.0); plan.len()]; partitions.len()];
+        Partitioned {
+            block_size,
+            plan,
+            partitions,
+            spectra,
+            newest: 0,
+            history: vec![0f64; 2 * block_size],
+            sum: vec![Complex::new(0.0, 0.0); 2 * block_size],
+        }
+    }
+
+    /// Number of partitions the impulse response was split into.
+    pub fn num_partitions(&self) -> usize {
+        self.partitions.len()
+    }
+
+    /// Convolves the next block of input (`block_size` samples) into `output`.
+    pub fn process_block(&mut self, input: &[f64], output: &mut [f64]) {
+        assert!(input.len() == self.block_size && output.len() == self.block_size);
+
+        // Transform the last two blocks of input, and add the result to the delay line (over the
+        // oldest spectrum):
+        let size = self.block_size;
+        for i in 0..size {
+            self.history[i] = self.history[i + size];
+        }
+        self.history[size..].copy_from_slice(input);
+        self.newest = (self.newest + self.spectra.len() - 1) % self.spectra.len();
+        transform_into(&self.plan, &self.history, &mut self.spectra[self.newest]);
+
+        // Multiply each input spectrum with the partition matching its age, and add them all up:
+        for s in self.sum.iter_mut() {
+            *s = Complex::new(0.0, 0.0);
+        }
+        for (age, partition) in self.partitions.iter().enumerate() {
+            let spectrum = &self.spectra[(self.newest + age) % self.spectra.len()];
+            for ((s, x), h) in self.sum.iter_mut().zip(spectrum).zip(partition) {
+                *s = *s + *x * *h;
+            }
+        }
+
+        // The first half wrapped around; the second half is the output:
+        inverse_transform(&self.plan, &mut self.sum);
+        for (y, x) in output.iter_mut().zip(&self.sum[size..]) {
+            *y = x.re;
+        }
+    }
+}
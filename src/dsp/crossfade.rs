@@ -0,0 +1,109 @@
+//! Signal that crossfades between two other signals
+//!
+//! The position goes from 0.0 (only the first signal) to 1.0 (only the second signal), and can be
+//! a constant or another signal. A linear crossfade keeps the *amplitudes* adding up to 1.0, which
+//! is right for signals that are in phase with each other (e.g. two versions of the same sound);
+//! an equal-power crossfade keeps the *power* constant, which avoids a dip in loudness halfway
+//! through when fading between unrelated signals.
+
+use std::f64;
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+use dsp::parameter::Parameter;
+
+/// Crossfade curve.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CrossfadeLaw {
+    /// Gains of (1 - position) and position (-6dB each in the middle)
+    Linear,
+    /// Gains of cos(position * pi/2) and sin(position * pi/2) (-3dB each in the middle)
+    EqualPower,
+}
+
+/// Crossfade struct
+pub struct Crossfade {
+    first: Box<Signal>,   // Signal heard at position 0.0
+    second: Box<Signal>,  // Signal heard at position 1.0
+    position: Parameter,  // Crossfade position (0.0 to 1.0)
+    law: CrossfadeLaw,
+}
+
+impl Crossfade {
+    /// Creates a new Crossfade signal
+    pub fn new<P: Into<Parameter>>(first: Box<Signal>, second: Box<Signal>, position: P,
+                                   law: CrossfadeLaw) -> Crossfade {
+        Crossfade {first, second, position: position.into(), law}
+    }
+
+    /// Sets a new crossfade position
+    pub fn set_position<P: Into<Parameter>>(&mut self, position: P) {
+        self.position = position.into();
+    }
+}
+
+signal_ops!(Crossfade);
+
+impl Signal for Crossfade {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.first.prepare(context);
+        self.second.prepare(context);
+        self.position.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let position = self.position.evaluate().clamp(0.0, 1.0);
+        let (first_gain, second_gain) = match self.law {
+            CrossfadeLaw::Linear => (1.0 - position, position),
+            CrossfadeLaw::EqualPower => {
+                let angle = position * f64::consts::FRAC_PI_2;
+                (angle.cos(), angle.sin())
+            },
+        };
+
+        // Both signals keep running even when they can't be heard, so they stay in sync
+        self.first.evaluate() * first_gain + self.second.evaluate() * second_gain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dsp::generators::White;
+
+    /// Gains the crossfade gives its first and second signals at `position`.
+    fn gains(position: f64, law: CrossfadeLaw) -> (f64, f64) {
+        // (White noise with no amplitude is just its offset)
+        let one = || Box::new(White::new(0.0, 1.0, 0));
+        let zero = || Box::new(White::new(0.0, 0.0, 0));
+        (Crossfade::new(one(), zero(), position, law).evaluate(),
+         Crossfade::new(zero(), one(), position, law).evaluate())
+    }
+
+    #[test]
+    fn laws_in_the_middle() {
+        let (first, second) = gains(0.5, CrossfadeLaw::Linear);
+        assert!((first - 0.5).abs() < 1e-12 && (second - 0.5).abs() < 1e-12);
+
+        let (first, second) = gains(0.5, CrossfadeLaw::EqualPower);
+        assert!((first - f64::consts::FRAC_1_SQRT_2).abs() < 1e-12);
+        assert!((second - f64::consts::FRAC_1_SQRT_2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn equal_power_keeps_the_power_constant() {
+        for i in 0..11 {
+            let (first, second) = gains(i as f64 / 10.0, CrossfadeLaw::EqualPower);
+            assert!((first * first + second * second - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn positions_outside_the_range_are_clamped() {
+        for &law in &[CrossfadeLaw::Linear, CrossfadeLaw::EqualPower] {
+            let (first, second) = gains(-0.5, law);
+            assert!((first - 1.0).abs() < 1e-12 && second.abs() < 1e-12);
+            let (first, second) = gains(1.5, law);
+            assert!(first.abs() < 1e-12 && (second - 1.0).abs() < 1e-12);
+        }
+    }
+}
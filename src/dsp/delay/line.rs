@@ -0,0 +1,76 @@
+//! Delay line buffer.
+//!
+//! A circular buffer that remembers the most recent samples written to it. Reading doesn't
+//! consume anything, so any number of taps can read from the same line at different delays.
+
+// Extra samples kept on top of the requested maximum delay, so that interpolating around the
+// longest delay still has neighbours to use:
+const INTERPOLATION_ROOM: usize = 32;
+
+/// Delay line struct.
+pub struct DelayLine {
+    buffer: Vec<f64>,  // Power-of-two length, so wrapping around is just a mask
+    position: usize,   // Where the next sample gets written
+}
+
+impl DelayLine {
+    /// Creates a new (silent) DelayLine that can delay by at least `max_delay` samples.
+    pub fn new(max_delay: usize) -> DelayLine {
+        DelayLine {
+            buffer: vec![0f64; (max_delay + INTERPOLATION_ROOM).next_power_of_two()],
+            position: 0,
+        }
+    }
+
+    /// Adds a sample to the line (the oldest sample drops out).
+    pub fn write(&mut self, sample: f64) {
+        self.position = (self.position + 1) & (self.buffer.len() - 1);
+        self.buffer[self.position] = sample;
+    }
+
+    /// The sample written `delay` writes ago: 1 is the most recently written sample, 2 the one
+    /// before that, and so on.
+    ///
+    /// Reading before writing the current input means a delay of `delay` samples; that's how
+    /// `Tap` reads (and why delays of less than one sample aren't possible, so `delay` has to be
+    /// at least 1).
+    pub fn sample(&self, delay: usize) -> f64 {
+        assert!(delay >= 1, "Delay lines can't be read at a delay of less than one sample");
+        let mask = self.buffer.len() - 1;
+        self.buffer[(self.position + self.buffer.len() - ((delay - 1) & mask)) & mask]
+    }
+
+    /// Number of samples the line holds (the longest delay that can be read).
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Fills the line with silence.
+    pub fn clear(&mut self) {
+        for sample in self.buffer.iter_mut() {
+            *sample = 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_back_what_was_written() {
+        let mut line = DelayLine::new(10);
+        for n in 0..100 {
+            line.write(n as f64);
+        }
+        for delay in 1..line.capacity() + 1 {
+            assert_eq!(line.sample(delay), (100 - delay) as f64);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn delay_has_to_be_at_least_one_sample() {
+        DelayLine::new(10).sample(0);
+    }
+}
@@ -0,0 +1,195 @@
+//! Delays.
+//!
+//! The building blocks are a `DelayLine` (a buffer of the most recent samples) and any number of
+//! `Tap`s reading it at fractional, modulatable delays. `Delay` puts them together as a signal,
+//! with the taps' output fed back into the line, which covers most delay-based effects:
+//!
+//!  - Echo: one long tap (hundreds of milliseconds) with some feedback
+//!  - Chorus: a few taps around 20ms, each modulated by a slow LFO, mixed with the dry signal
+//!  - Flanger: one short tap (a few milliseconds) swept by an LFO, with feedback
+//!  - Comb filter: one short fixed tap with lots of feedback (resonates at 1 / delay Hz), which
+//!    is also the core of plucked-string physical models
+//!
+//! Delay times are in seconds.
+
+use dsp::traits::Signal;
+use dsp::context::{ProcessContext, DEFAULT_SAMPLE_RATE};
+use dsp::parameter::Parameter;
+
+// Circular buffer
+pub mod line;
+pub use self::line::DelayLine;
+
+// Reading the buffer at fractional delays
+pub mod tap;
+pub use self::tap::{Tap, Interpolation};
+
+/// Delay effect struct.
+pub struct Delay {
+    sample_rate: f64,       // Sample rate (for audio playback, etc) - Set by prepare()
+    filtered: Box<Signal>,  // Input signal
+    max_delay: f64,         // Longest delay the line has to hold (in seconds)
+    line: DelayLine,
+    taps: Vec<Tap>,
+    feedback: Parameter,    // How much of the taps' output goes back into the line
+    dry: Parameter,         // How much of the input goes straight to the output
+}
+
+impl Delay {
+    /// Creates a new Delay with a single tap.
+    ///
+    /// `max_delay` (in seconds) is the longest the delay will ever get; the delay itself, and the
+    /// feedback, can be either an `f64` (a constant value) or another signal, which gets evaluated
+    /// every sample. The output is only the delayed signal (see `set_dry`).
+    pub fn new<D, F>(filtered: Box<Signal>, max_delay: f64, delay: D, feedback: F) -> Delay
+        where D: Into<Parameter>, F: Into<Parameter> {
+        Delay::multi_tap(filtered, max_delay, vec![Tap::new(delay, 1.0)], feedback)
+    }
+
+    /// Creates a new Delay with several taps. Their outputs are added together, and that sum is
+    /// what gets fed back.
+    pub fn multi_tap<F: Into<Parameter>>(filtered: Box<Signal>, max_delay: f64, taps: Vec<Tap>,
+                                         feedback: F) -> Delay {
+        assert!(max_delay >= 0.0);
+        Delay {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            filtered,
+            max_delay,
+            line: DelayLine::new((max_delay * DEFAULT_SAMPLE_RATE).ceil() as usize),
+            taps,
+            feedback: feedback.into(),
+            dry: Parameter::Constant(0.0),
+        }
+    }
+
+    /// Sets how much of the input gets mixed into the output, undelayed (the default is none).
+    pub fn set_dry<D: Into<Parameter>>(&mut self, dry: D) {
+        self.dry = dry.into();
+    }
+
+    /// Chooses how every tap reads between samples (see `Tap::set_interpolation`).
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) {
+        for tap in self.taps.iter_mut() {
+            tap.set_interpolation(interpolation);
+        }
+    }
+
+    /// The delay's taps (e.g. to change their interpolation one by one).
+    pub fn taps_mut(&mut self) -> &mut [Tap] {
+        &mut self.taps
+    }
+
+    /// Silences the delay line (the taps' modulation carries on where it was).
+    pub fn clear(&mut self) {
+        self.line.clear();
+    }
+
+    /// Delays one sample.
+    fn process(&mut self, input: f64) -> f64 {
+        let mut wet = 0.0;
+        for tap in self.taps.iter_mut() {
+            wet += tap.read(&self.line);
+        }
+        let feedback = self.feedback.evaluate();
+        self.line.write(input + feedback * wet);
+        self.dry.evaluate() * input + wet
+    }
+}
+
+signal_ops!(Delay);
+
+impl Signal for Delay {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.filtered.prepare(context);
+        self.feedback.prepare(context);
+        self.dry.prepare(context);
+        for tap in self.taps.iter_mut() {
+            tap.prepare(context);
+        }
+        if context.sample_rate != self.sample_rate {
+            self.sample_rate = context.sample_rate;
+            self.line = DelayLine::new((self.max_delay * self.sample_rate).ceil() as usize);
+        }
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let input = self.filtered.evaluate();
+        self.process(input)
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        self.filtered.evaluate_block(buffer);
+        for sample in buffer.iter_mut() {
+            *sample = self.process(*sample);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64;
+    use super::*;
+    use dsp::filters::tests::Impulse;
+    use dsp::generators::Sine;
+    use dsp::ops::SignalExt;
+
+    // 1024 samples per second, so delays in 1024ths of a second are whole numbers of samples
+    const SAMPLE_RATE: f64 = 1024.0;
+
+    fn run(mut delay: Delay, length: usize) -> Vec<f64> {
+        delay.prepare(&ProcessContext::new(SAMPLE_RATE, 64));
+        let mut output = vec![0f64; length];
+        delay.evaluate_block(&mut output);
+        output
+    }
+
+    #[test]
+    fn echoes_fade_by_the_feedback() {
+        let delay = Delay::new(Box::new(Impulse::new()), 1.0, 100.0 / SAMPLE_RATE, 0.6);
+        let output = run(delay, 1000);
+        for (n, &sample) in output.iter().enumerate() {
+            // The kth echo (from 0) comes (k + 1) delays after the impulse
+            let echo = n > 0 && n % 100 == 0;
+            let expected = if echo { 0.6f64.powi(n as i32 / 100 - 1) } else { 0.0 };
+            assert!((sample - expected).abs() < 1e-12, "sample {}: {}, expected {}", n, sample,
+                    expected);
+        }
+    }
+
+    #[test]
+    fn modulated_delays_follow_the_modulation() {
+        // A 20Hz sine through a delay swept between 5ms and 15ms by a 3Hz LFO
+        let w = 2.0 * f64::consts::PI * 20.0 / SAMPLE_RATE;
+        let input = Sine::new(1.0, 20.0, 0.0).boxed();
+        let delay = Delay::new(input, 0.05, Sine::new(0.005, 3.0, 0.01), 0.0);
+        let output = run(delay, 4096);
+
+        let mut lfo = Sine::new(0.005, 3.0, 0.01);
+        lfo.prepare(&ProcessContext::new(SAMPLE_RATE, 64));
+        for (n, &sample) in output.iter().enumerate() {
+            let expected = (w * (n as f64 - lfo.evaluate() * SAMPLE_RATE)).sin();
+            if n >= 100 {
+                assert!((sample - expected).abs() < 1e-4, "sample {}: {}, expected {}", n, sample,
+                        expected);
+            }
+        }
+    }
+
+    #[test]
+    fn taps_are_summed_and_fed_back_together() {
+        let taps = vec![Tap::new(10.0 / SAMPLE_RATE, 0.5), Tap::new(25.0 / SAMPLE_RATE, -0.25)];
+        let mut delay = Delay::multi_tap(Box::new(Impulse::new()), 1.0, taps, 0.5);
+        delay.set_dry(1.0);
+        let output = run(delay, 500);
+
+        // What the line holds and what the taps read, worked out directly
+        let mut line = vec![0f64; 500];
+        for n in 0..500 {
+            let tapped = |d: usize| if n >= d { line[n - d] } else { 0.0 };
+            let wet = 0.5 * tapped(10) - 0.25 * tapped(25);
+            let input = if n == 0 { 1.0 } else { 0.0 };
+            line[n] = input + 0.5 * wet;
+            assert!((output[n] - (input + wet)).abs() < 1e-12, "sample {}", n);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Complex number type used by the FFT engine.
+//!
+//! The rest of the `dft` module hands spectra around as separate real/imaginary vectors (the
+//! "rectangular" form), but the FFT itself is much easier to write in terms of single complex
+//! values, so this is the minimal type it needs. (Filter frequency responses use it too.)
+
+use std::f64;
+use std::ops::{Add, Sub, Mul, Div, Neg};
+
+/// A complex number, stored in rectangular form.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Complex {
+    /// Real part
+    pub re: f64,
+    /// Imaginary part
+    pub im: f64,
+}
+
+impl Complex {
+    /// Creates a new complex number from its real and imaginary parts.
+    pub fn new(re: f64, im: f64) -> Complex {
+        Complex {re, im}
+    }
+
+    /// Creates a complex number with a magnitude of 1.0 and the given angle (in radians).
+    pub fn from_angle(angle: f64) -> Complex {
+        Complex {re: angle.cos(), im: angle.sin()}
+    }
+
+    /// Complex conjugate (negates the imaginary part).
+    pub fn conj(self) -> Complex {
+        Complex {re: self.re, im: -self.im}
+    }
+
+    /// Multiplies both parts by a real number.
+    pub fn scale(self, factor: f64) -> Complex {
+        Complex {re: self.re * factor, im: self.im * factor}
+    }
+
+    /// Magnitude (absolute value) of the complex number.
+    pub fn norm(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    /// Angle (argument) of the complex number, in radians, in the range (-pi, pi].
+    pub fn arg(self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    /// Principal square root (the one with a non-negative real part).
+    pub fn sqrt(self) -> Complex {
+        let norm = self.norm();
+        let re = ((norm + self.re) / 2.0).sqrt();
+        let im = ((norm - self.re) / 2.0).sqrt();
+        Complex {re, im: if self.im < 0.0 { -im } else { im }}
+    }
+
+    /// e raised to the complex number.
+    pub fn exp(self) -> Complex {
+        Complex::from_angle(self.im).scale(self.re.exp())
+    }
+
+    /// Principal natural logarithm.
+    pub fn ln(self) -> Complex {
+        Complex {re: self.norm().ln(), im: self.arg()}
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, other: Complex) -> Complex {
+        Complex {re: self.re + other.re, im: self.im + other.im}
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, other: Complex) -> Complex {
+        Complex {re: self.re - other.re, im: self.im - other.im}
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, other: Complex) -> Complex {
+        Complex {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+    fn div(self, other: Complex) -> Complex {
+        let norm_squared = other.re * other.re + other.im * other.im;
+        Complex {
+            re: (self.re * other.re + self.im * other.im) / norm_squared,
+            im: (self.im * other.re - self.re * other.im) / norm_squared,
+        }
+    }
+}
+
+impl Neg for Complex {
+    type Output = Complex;
+    fn neg(self) -> Complex {
+        Complex {re: -self.re, im: -self.im}
+    }
+}
@@ -0,0 +1,336 @@
+//! Fast Fourier Transform engine.
+//!
+//! The straightforward DFT (two nested loops, one `cos`/`sin` per term) is O(N^2), which makes it
+//! unusable for anything longer than a few thousand samples. This module computes exactly the
+//! same transform in O(N log N):
+//!
+//!  - Lengths that factor into small primes (2, 3, 5, 7, ...) use a recursive mixed-radix
+//!    Cooley-Tukey FFT. Radix 2 gets its own butterfly, every other radix uses a generic one.
+//!  - Lengths with a large prime factor use Bluestein's algorithm, which re-expresses the DFT as a
+//!    convolution and computes that convolution with a power-of-two FFT.
+//!
+//! All the twiddle factors (and, for Bluestein, the chirp and its transform) are computed once
+//! when an `FftPlan` is created, along with the scratch space the transform needs, so a plan
+//! should be reused for every transform of that length. Transforming in place (`process` and
+//! `process_inverse`) doesn't allocate; the real-signal versions return new vectors.
+//!
+//! Conventions: the forward transform is X[k] = sum(x[n] * e^(-2*pi*i*k*n/N)), and the inverse
+//! transform is the same thing with a positive exponent and *no* 1/N scaling.
+
+use std::f64;
+use std::cell::RefCell;
+use dsp::dft::Complex;
+
+// Any prime factor larger than this gets handled by Bluestein's algorithm instead of the generic
+// O(p^2) butterfly:
+const MAX_MIXED_RADIX_FACTOR: usize = 31;
+
+/// A precomputed FFT of a fixed length.
+pub struct FftPlan {
+    len: usize,
+    algorithm: Algorithm,
+}
+
+enum Algorithm {
+    // Lengths 0 and 1 (nothing to do)
+    Trivial,
+
+    // Mixed-radix Cooley-Tukey
+    MixedRadix {
+        factors: Vec<usize>,     // Prime factors of `len`, in the order they get used
+        twiddles: Vec<Complex>,  // e^(-2*pi*i*k/len) for k in 0..len
+        input: RefCell<Vec<Complex>>,  // Copy of the input (the recursion reads it with strides)
+    },
+
+    // Bluestein's chirp-z algorithm
+    Bluestein {
+        inner: Box<FftPlan>,          // Power-of-two FFT used for the convolution
+        chirp: Vec<Complex>,          // e^(-pi*i*k^2/len) for k in 0..len
+        kernel_fft: Vec<Complex>,     // FFT of the (conjugated, wrapped-around) chirp
+        scratch: RefCell<Vec<Complex>>,  // Convolution buffer, inner.len() long
+    },
+}
+
+impl FftPlan {
+    /// Creates a new FFT plan for signals of length `len`.
+    pub fn new(len: usize) -> FftPlan {
+        if len <= 1 {
+            return FftPlan {len, algorithm: Algorithm::Trivial};
+        }
+
+        let factors = factorize(len);
+        if *factors.last().unwrap() <= MAX_MIXED_RADIX_FACTOR {
+            FftPlan {
+                len,
+                algorithm: Algorithm::MixedRadix {
+                    factors,
+                    twiddles: twiddle_table(len),
+                    input: RefCell::new(vec![Complex::new(0.0, 0.0); len]),
+                },
+            }
+        }
+        else {
+            FftPlan::new_bluestein(len)
+        }
+    }
+
+    fn new_bluestein(len: usize) -> FftPlan {
+        // The convolution is (2*len - 1) samples long; pad it up to a power of two so it can't
+        // itself need Bluestein:
+        let inner_len = (2 * len - 1).next_power_of_two();
+        let inner = FftPlan::new(inner_len);
+
+        // k^2 gets huge quickly, so reduce it modulo 2*len before converting to an angle to keep
+        // the chirp accurate:
+        let chirp: Vec<Complex> = (0..len)
+            .map(|k| {
+                let k_squared = (k * k) % (2 * len);
+                Complex::from_angle(-f64::consts::PI * (k_squared as f64) / (len as f64))
+            })
+            .collect();
+
+        let mut kernel_fft = vec![Complex::new(0.0, 0.0); inner_len];
+        kernel_fft[0] = chirp[0].conj();
+        for k in 1..len {
+            kernel_fft[k] = chirp[k].conj();
+            kernel_fft[inner_len - k] = chirp[k].conj();
+        }
+        inner.process(&mut kernel_fft);
+
+        FftPlan {
+            len,
+            algorithm: Algorithm::Bluestein {
+                inner: Box::new(inner),
+                chirp,
+                kernel_fft,
+                scratch: RefCell::new(vec![Complex::new(0.0, 0.0); inner_len]),
+            },
+        }
+    }
+
+    /// Length of the signals this plan transforms.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether this plan transforms empty signals.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Computes the forward FFT of `buffer`, in place.
+    pub fn process(&self, buffer: &mut [Complex]) {
+        assert!(buffer.len() == self.len);
+
+        match self.algorithm {
+            Algorithm::Trivial => {},
+            Algorithm::MixedRadix {ref factors, ref twiddles, ref input} => {
+                let mut input = input.borrow_mut();
+                input.copy_from_slice(buffer);
+                mixed_radix(&input, 1, buffer, factors, twiddles, 1);
+            },
+            Algorithm::Bluestein {ref inner, ref chirp, ref kernel_fft, ref scratch} => {
+                let mut scratch = scratch.borrow_mut();
+                for k in 0..self.len {
+                    scratch[k] = buffer[k] * chirp[k];
+                }
+                for x in scratch[self.len..].iter_mut() {
+                    *x = Complex::new(0.0, 0.0);
+                }
+
+                // Convolve with the chirp kernel (multiply in the frequency domain):
+                inner.process(&mut scratch);
+                for k in 0..scratch.len() {
+                    scratch[k] = scratch[k] * kernel_fft[k];
+                }
+                inner.process_inverse(&mut scratch);
+
+                let scale = 1.0 / (inner.len() as f64);
+                for k in 0..self.len {
+                    buffer[k] = (scratch[k] * chirp[k]).scale(scale);
+                }
+            },
+        }
+    }
+
+    /// Computes the (unscaled) inverse FFT of `buffer`, in place.
+    ///
+    /// Running `process` followed by `process_inverse` multiplies every sample by the length of
+    /// the plan; divide by `len()` to get the original signal back.
+    pub fn process_inverse(&self, buffer: &mut [Complex]) {
+        // The inverse DFT is the forward DFT with the sign of the exponent flipped, which is the
+        // same as conjugating on the way in and on the way out:
+        for x in buffer.iter_mut() {
+            *x = x.conj();
+        }
+        self.process(buffer);
+        for x in buffer.iter_mut() {
+            *x = x.conj();
+        }
+    }
+
+    /// Computes the FFT of a real signal and returns the first `len/2 + 1` bins in rectangular
+    /// form (the remaining bins are just the complex conjugates of these).
+    pub fn process_real(&self, signal: &[f64]) -> (Vec<f64>, Vec<f64>) {
+        let mut buffer: Vec<Complex> = signal.iter().map(|&x| Complex::new(x, 0.0)).collect();
+        self.process(&mut buffer);
+
+        let result_length = self.len / 2 + 1;
+        let mut re_x = vec![0f64; result_length];
+        let mut im_x = vec![0f64; result_length];
+        for (k, x) in buffer.iter().take(result_length).enumerate() {
+            re_x[k] = x.re;
+            im_x[k] = x.im;
+        }
+
+        (re_x, im_x)
+    }
+
+    /// Inverse of `process_real`: rebuilds a real signal of length `len` from its first
+    /// `len/2 + 1` bins, including the 1/N scaling.
+    ///
+    /// The missing upper half of the spectrum is filled in as the complex conjugate of the lower
+    /// half. The imaginary parts of the DC bin (and the Nyquist bin, for even lengths) can't be
+    /// represented by a real signal, so they are ignored.
+    pub fn process_real_inverse(&self, real: &[f64], imaginary: &[f64]) -> Vec<f64> {
+        let result_length = self.len / 2 + 1;
+        assert!(real.len() == result_length && imaginary.len() == result_length);
+        if self.len == 0 {
+            return vec![];
+        }
+
+        let mut buffer = vec![Complex::new(0.0, 0.0); self.len];
+        buffer[0] = Complex::new(real[0], 0.0);
+        for k in 1..result_length {
+            buffer[k] = Complex::new(real[k], imaginary[k]);
+            buffer[self.len - k] = buffer[k].conj();
+        }
+        if self.len.is_multiple_of(2) {
+            buffer[self.len / 2] = Complex::new(real[self.len / 2], 0.0);
+        }
+
+        self.process_inverse(&mut buffer);
+
+        let scale = 1.0 / (self.len as f64);
+        buffer.iter().map(|x| x.re * scale).collect()
+    }
+}
+
+/// Splits `n` into its prime factors, smallest first (so the cheap radix-2 butterflies run on the
+/// biggest sub-transforms).
+fn factorize(mut n: usize) -> Vec<usize> {
+    let mut factors = vec![];
+    let mut p = 2;
+    while p * p <= n {
+        while n.is_multiple_of(p) {
+            factors.push(p);
+            n /= p;
+        }
+        p += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors
+}
+
+/// Builds the table of e^(-2*pi*i*k/len) for k in 0..len.
+fn twiddle_table(len: usize) -> Vec<Complex> {
+    (0..len)
+        .map(|k| Complex::from_angle(-2.0 * f64::consts::PI * (k as f64) / (len as f64)))
+        .collect()
+}
+
+/// Recursive decimation-in-time mixed-radix FFT.
+///
+/// Transforms every `stride`th element of `input` into `output`. `twiddles` is the table for the
+/// *full* transform length, so the twiddles for this sub-transform are every `tw_stride`th entry.
+fn mixed_radix(input: &[Complex], stride: usize, output: &mut [Complex], factors: &[usize],
+               twiddles: &[Complex], tw_stride: usize) {
+    let n = output.len();
+    let p = factors[0];
+    let m = n / p;
+
+    if m == 1 {
+        // Base case: plain DFT of length p
+        for k in 0..p {
+            let mut sum = Complex::new(0.0, 0.0);
+            for j in 0..p {
+                sum = sum + input[j * stride] * twiddles[((j * k) % p) * tw_stride];
+            }
+            output[k] = sum;
+        }
+        return;
+    }
+
+    // Transform each of the p interleaved sub-sequences into its own chunk of the output:
+    for q in 0..p {
+        mixed_radix(&input[q * stride..], stride * p, &mut output[q * m..(q + 1) * m],
+                    &factors[1..], twiddles, tw_stride * p);
+    }
+
+    // Combine the sub-transforms with radix-p butterflies:
+    if p == 2 {
+        for k in 0..m {
+            let a = output[k];
+            let b = output[k + m] * twiddles[k * tw_stride];
+            output[k] = a + b;
+            output[k + m] = a - b;
+        }
+    }
+    else {
+        // (p is at most MAX_MIXED_RADIX_FACTOR, so this fits on the stack)
+        let mut scratch = [Complex::new(0.0, 0.0); MAX_MIXED_RADIX_FACTOR];
+        for k in 0..m {
+            for q in 0..p {
+                scratch[q] = output[q * m + k] * twiddles[q * k * tw_stride];
+            }
+            for r in 0..p {
+                let mut sum = scratch[0];
+                for q in 1..p {
+                    sum = sum + scratch[q] * twiddles[((q * r) % p) * m * tw_stride];
+                }
+                output[r * m + k] = sum;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dsp::dft::{Complex, FftPlan};
+    use dsp::generators::{Sine, Saw, Square};
+    use dsp::traits::Signal;
+
+    /// Largest difference between a block and its forward + inverse transform.
+    fn round_trip_error(signal: &mut Signal, len: usize) -> f64 {
+        let mut block = vec![0f64; len];
+        signal.evaluate_block(&mut block);
+
+        let plan = FftPlan::new(len);
+        let mut buffer: Vec<Complex> = block.iter().map(|&x| Complex::new(x, 0.0)).collect();
+        plan.process(&mut buffer);
+        plan.process_inverse(&mut buffer);
+
+        let scale = 1.0 / len as f64;
+        buffer.iter().zip(&block)
+            .map(|(x, &y)| (x.re * scale - y).abs().max((x.im * scale).abs()))
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn round_trip_generators() {
+        // Power of two, mixed radix (2^3 * 3^2 * 5 * 7), and primes (Bluestein)
+        for &len in [1024, 2520, 1021, 4099].iter() {
+            let mut signals: Vec<Box<Signal>> = vec![
+                Box::new(Sine::new(1.0, 440.0, 0.0)),
+                Box::new(Saw::new(0.5, 1234.5, 0.1)),
+                Box::new(Square::new(0.8, 98.7, -0.2)),
+            ];
+            for signal in signals.iter_mut() {
+                let error = round_trip_error(&mut **signal, len);
+                assert!(error < 1e-9, "len {}: round trip error {}", len, error);
+            }
+        }
+    }
+}
@@ -1,29 +1,34 @@
 //! Discrete Fourier Transform
+//!
+//! The transforms themselves are computed by the FFT engine in `fft`; the functions here are
+//! convenience wrappers that take and return plain vectors.
 use std::f64;
 
-/// Turn an arbitrary vector into a Rectangular DFT result
-pub fn vec_to_rectangular(signal: Vec<f64>) -> (Vec<f64>, Vec<f64>) {
-    let signal_length = signal.len();
-    let result_length = signal.len() / 2 + 1;
-    let mut re_x = vec![0f64; result_length];
-    let mut im_x = vec![0f64; result_length];
+// Complex number type used by the FFT
+pub mod complex;
+pub use self::complex::Complex;
 
-    for k in 0..result_length {
-        for i in 0..signal_length {;
-            re_x[k] += signal[i] * (f64::consts::PI * 2.0 * (k as f64) * (i as f64) / (signal_length as f64)).cos()
-        }
-    }
+// FFT engine (mixed-radix + Bluestein)
+pub mod fft;
+pub use self::fft::FftPlan;
 
-    for k in 0..result_length {
-        for i in 0..signal_length {;
-            im_x[k] += -1.0 * signal[i] * (f64::consts::PI * 2.0 * (k as f64) * (i as f64) / (signal_length as f64)).sin()
-        }
+// Short-time Fourier transform
+pub mod stft;
+pub use self::stft::Stft;
+
+/// Turn an arbitrary vector into a Rectangular DFT result
+pub fn vec_to_rectangular(signal: Vec<f64>) -> (Vec<f64>, Vec<f64>) {
+    if signal.is_empty() {
+        return (vec![0f64; 1], vec![0f64; 1]);
     }
 
-    (re_x, im_x)
+    FftPlan::new(signal.len()).process_real(&signal)
 }
 
 /// Turn a Rectangular DFT result into a Polar DFT result
+///
+/// Phases are in radians, in the range (-pi, pi]. A bin with zero magnitude has no meaningful
+/// phase, so it is reported as 0.0.
 pub fn rectangular_to_polar(real: Vec<f64>, imaginary: Vec<f64>) -> (Vec<f64>, Vec<f64>) {
     assert!(real.len() == imaginary.len());
     let result_length = real.len();
@@ -34,24 +39,198 @@ pub fn rectangular_to_polar(real: Vec<f64>, imaginary: Vec<f64>) -> (Vec<f64>, V
     for k in 0..result_length {
         mag_x[k] = ((real[k]).powi(2) + (imaginary[k]).powi(2)).sqrt();
 
-        if real[k] == 0.0 {
-            if imaginary[k] < 0.0 {
-                phase_x[k] = -1.0 * f64::consts::PI / 2.0
-            }
-            else {
-                phase_x[k] = f64::consts::PI / 2.0
-            }
+        if real[k] == 0.0 && imaginary[k] == 0.0 {
+            phase_x[k] = 0.0
         }
         else {
-            phase_x[k] = (imaginary[k] / real[k]).atan()
+            // atan2 looks at the signs of both parts, so it gets the quadrant right (a plain
+            // atan(im/re) folds everything into (-pi/2, pi/2)):
+            phase_x[k] = imaginary[k].atan2(real[k])
         }
     }
 
     (mag_x, phase_x)
 }
 
+/// Unwrap a phase vector (in radians) by removing the 2*pi jumps between consecutive values.
+///
+/// `rectangular_to_polar` can only report phases in (-pi, pi], so a phase that keeps increasing
+/// (or decreasing) wraps around every time it crosses pi. This adds/subtracts multiples of 2*pi
+/// so that no two neighbouring values differ by more than pi.
+pub fn unwrap_phase(phase: Vec<f64>) -> Vec<f64> {
+    let mut unwrapped = Vec::with_capacity(phase.len());
+    let mut correction = 0.0;
+
+    for k in 0..phase.len() {
+        if k > 0 {
+            let jump = phase[k] - phase[k - 1];
+            if !(-f64::consts::PI..=f64::consts::PI).contains(&jump) {
+                correction -= 2.0 * f64::consts::PI * (jump / (2.0 * f64::consts::PI)).round();
+            }
+        }
+        unwrapped.push(phase[k] + correction);
+    }
+
+    unwrapped
+}
+
+/// Compute the group delay (in samples) of each bin from a Polar DFT phase result.
+///
+/// Group delay is the negative derivative of the (unwrapped) phase with respect to frequency. The
+/// bins are 2*pi/N radians/sample apart, where N is the length of the original signal, so that has
+/// to be passed in as `signal_length`. Interior bins use a central difference, and the two end
+/// bins use a one-sided difference.
+pub fn group_delay(phase: Vec<f64>, signal_length: usize) -> Vec<f64> {
+    let phase = unwrap_phase(phase);
+    let bins = phase.len();
+    let bin_width = 2.0 * f64::consts::PI / (signal_length as f64);
+
+    let mut delay = vec![0f64; bins];
+    if bins < 2 {
+        return delay;
+    }
+
+    for (k, delay) in delay.iter_mut().enumerate() {
+        let (lower, upper) = match k {
+            0 => (0, 1),
+            k if k == bins - 1 => (k - 1, k),
+            k => (k - 1, k + 1),
+        };
+        *delay = -(phase[upper] - phase[lower]) / (((upper - lower) as f64) * bin_width);
+    }
+
+    delay
+}
+
 /// Turn an arbitrary vector into a Polar DFT result
 pub fn vec_to_polar(signal: Vec<f64>) -> (Vec<f64>, Vec<f64>) {
     let (real, imaginary) = vec_to_rectangular(signal);
     rectangular_to_polar(real, imaginary)
-}
\ No newline at end of file
+}
+
+/// Turn a Polar DFT result back into a Rectangular DFT result
+pub fn polar_to_rectangular(magnitude: Vec<f64>, phase: Vec<f64>) -> (Vec<f64>, Vec<f64>) {
+    assert!(magnitude.len() == phase.len());
+
+    let real = magnitude.iter().zip(phase.iter()).map(|(m, p)| m * p.cos()).collect();
+    let imaginary = magnitude.iter().zip(phase.iter()).map(|(m, p)| m * p.sin()).collect();
+
+    (real, imaginary)
+}
+
+/// Turn a Rectangular DFT result back into the time-domain signal it came from (inverse DFT).
+///
+/// A signal of length N and one of length N+1 both produce N/2+1 bins (for even N), so the
+/// length of the original signal has to be passed in as `signal_length`.
+pub fn rectangular_to_vec(real: Vec<f64>, imaginary: Vec<f64>, signal_length: usize) -> Vec<f64> {
+    assert!(real.len() == imaginary.len());
+    assert!(real.len() == signal_length / 2 + 1);
+    FftPlan::new(signal_length).process_real_inverse(&real, &imaginary)
+}
+
+/// Turn a Polar DFT result back into the time-domain signal it came from (inverse DFT).
+///
+/// See `rectangular_to_vec` for why `signal_length` is needed.
+pub fn polar_to_vec(magnitude: Vec<f64>, phase: Vec<f64>, signal_length: usize) -> Vec<f64> {
+    let (real, imaginary) = polar_to_rectangular(magnitude, phase);
+    rectangular_to_vec(real, imaginary, signal_length)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64;
+    use super::*;
+
+    /// The original O(N^2) DFT, kept around to check the FFT against.
+    fn naive_dft(signal: &[f64]) -> (Vec<f64>, Vec<f64>) {
+        let n = signal.len();
+        let mut re_x = vec![0f64; n / 2 + 1];
+        let mut im_x = vec![0f64; n / 2 + 1];
+        for k in 0..(n / 2 + 1) {
+            for (i, x) in signal.iter().enumerate() {
+                let angle = 2.0 * f64::consts::PI * ((k * i) % n) as f64 / n as f64;
+                re_x[k] += x * angle.cos();
+                im_x[k] -= x * angle.sin();
+            }
+        }
+        (re_x, im_x)
+    }
+
+    /// Something with a bit of everything in it (not periodic in any of the lengths tested).
+    fn test_signal(len: usize) -> Vec<f64> {
+        (0..len).map(|i| (0.37 * i as f64).sin() + ((i * 7919) % 101) as f64 / 50.0 - 1.0).collect()
+    }
+
+    // Powers of two, mixed radix (up to radix 7), and primes on both sides of the Bluestein cutoff
+    const LENGTHS: [usize; 14] = [1, 2, 3, 5, 7, 8, 12, 13, 31, 60, 97, 128, 210, 257];
+
+    #[test]
+    fn fft_matches_naive_dft() {
+        for &len in LENGTHS.iter() {
+            let signal = test_signal(len);
+            let (re_x, im_x) = naive_dft(&signal);
+            let (fft_re, fft_im) = vec_to_rectangular(signal);
+            assert_eq!(fft_re.len(), re_x.len());
+            for k in 0..re_x.len() {
+                assert!((fft_re[k] - re_x[k]).abs() < 1e-9, "len {} bin {}: real part differs", len, k);
+                assert!((fft_im[k] - im_x[k]).abs() < 1e-9, "len {} bin {}: imaginary part differs", len, k);
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_fft_undoes_naive_dft() {
+        for &len in LENGTHS.iter() {
+            let signal = test_signal(len);
+            let (re_x, im_x) = naive_dft(&signal);
+            let resynthesized = rectangular_to_vec(re_x, im_x, len);
+            for (a, b) in resynthesized.iter().zip(&signal) {
+                assert!((a - b).abs() < 1e-9, "len {}: resynthesis differs", len);
+            }
+        }
+    }
+
+    #[test]
+    fn polar_phase_covers_all_four_quadrants() {
+        let (real, imaginary) = (vec![1.0, -1.0, -1.0, 1.0, -2.0], vec![1.0, 1.0, -1.0, -1.0, 0.0]);
+        let (magnitude, phase) = rectangular_to_polar(real, imaginary);
+        let quarter = f64::consts::FRAC_PI_4;
+        let expected = [quarter, 3.0 * quarter, -3.0 * quarter, -quarter, f64::consts::PI];
+        for k in 0..4 {
+            assert!((magnitude[k] - f64::consts::SQRT_2).abs() < 1e-12);
+            assert!((phase[k] - expected[k]).abs() < 1e-12);
+        }
+        // The negative real axis is at +pi, not -pi
+        assert_eq!(magnitude[4], 2.0);
+        assert_eq!(phase[4], expected[4]);
+    }
+
+    #[test]
+    fn zero_magnitude_has_zero_phase() {
+        let (magnitude, phase) = rectangular_to_polar(vec![0.0, -0.0], vec![0.0, -0.0]);
+        assert_eq!(magnitude, vec![0.0, 0.0]);
+        assert_eq!(phase, vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn unwrapping_a_phase_ramp() {
+        let ramp: Vec<f64> = (0..100).map(|k| -0.9 * k as f64).collect();
+        let wrapped: Vec<f64> = ramp.iter().map(|p| p.sin().atan2(p.cos())).collect();
+        assert!(wrapped.iter().all(|p| p.abs() <= f64::consts::PI));
+        for (a, b) in unwrap_phase(wrapped).iter().zip(&ramp) {
+            assert!((a - b).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn group_delay_of_a_pure_delay() {
+        for &delay in &[0, 1, 5, 17] {
+            let mut impulse = vec![0f64; 64];
+            impulse[delay] = 1.0;
+            let (_, phase) = vec_to_polar(impulse);
+            for d in group_delay(phase, 64) {
+                assert!((d - delay as f64).abs() < 1e-9, "{} is not {}", d, delay);
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Short-time Fourier transform
+//!
+//! A single DFT tells you which frequencies are in a signal, but not *when* they happen. The STFT
+//! chops the signal up into short overlapping frames, windows each one, and takes the DFT of each
+//! frame separately, giving a spectrum for every point in time (a spectrogram).
+//!
+//! Frames are centered on multiples of the hop size: frame t covers the samples from
+//! (t * hop_size - frame_size / 2) up to (t * hop_size + frame_size / 2), with zeros standing in
+//! for anything before the start or after the end of the signal. That way the very first and last
+//! samples sit in the middle of a frame instead of at the tapered-off edge of the window, and the
+//! inverse transform can reconstruct the whole signal.
+//!
+//! The inverse transform uses weighted overlap-add: each frame is transformed back, multiplied by
+//! the window again, added into place, and the sum is divided by the sum of the squared windows.
+//! This reconstructs the original signal (even after the frames have been edited) as long as the
+//! windows overlap enough that the squared-window sum never hits zero, e.g. a Hann window with a
+//! hop size of at most half the frame size.
+
+use dsp::dft::FftPlan;
+use dsp::window::Window;
+
+/// Short-time Fourier transform settings (and the FFT plan to go with them).
+pub struct Stft {
+    frame_size: usize,        // Number of signal samples in each frame
+    hop_size: usize,          // Number of samples between the starts of consecutive frames
+    fft_size: usize,          // Frame size after zero-padding (>= frame_size)
+    window: Vec<f64>,         // Window coefficients, frame_size long
+    plan: FftPlan,            // FFT of length fft_size
+}
+
+impl Stft {
+    /// Creates a new STFT.
+    ///
+    /// Each frame of `frame_size` samples is multiplied by `window`, zero-padded up to `fft_size`
+    /// samples (pass `frame_size` for no zero-padding), and transformed. Consecutive frames start
+    /// `hop_size` samples apart.
+    pub fn new(frame_size: usize, hop_size: usize, window: Window, fft_size: usize) -> Stft {
+        assert!(frame_size > 0 && hop_size > 0);
+        assert!(fft_size >= frame_size);
+
+        Stft {
+            frame_size,
+            hop_size,
+            fft_size,
+            window: window.periodic(frame_size),
+            plan: FftPlan::new(fft_size),
+        }
+    }
+
+    /// Number of signal samples in each frame.
+    pub fn frame_size(&self) -> usize {
+        self.frame_size
+    }
+
+    /// Number of samples between the starts of consecutive frames.
+    pub fn hop_size(&self) -> usize {
+        self.hop_size
+    }
+
+    /// Length of the DFT taken of each (zero-padded) frame.
+    pub fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    /// Number of frames the STFT of a signal of length `signal_length` has.
+    pub fn num_frames(&self, signal_length: usize) -> usize {
+        signal_length / self.hop_size + 1
+    }
+
+    /// Turn an arbitrary vector into a list of Rectangular DFT results, one per frame.
+    pub fn forward(&self, signal: &[f64]) -> Vec<(Vec<f64>, Vec<f64>)> {
+        let mut frame = vec![0f64; self.fft_size];
+        let mut frames = Vec::with_capacity(self.num_frames(signal.len()));
+
+        for t in 0..self.num_frames(signal.len()) {
+            let start = (t * self.hop_size) as isize - (self.frame_size / 2) as isize;
+            for (i, (x, w)) in frame.iter_mut().zip(&self.window).enumerate() {
+                let n = start + i as isize;
+                *x = if n >= 0 && (n as usize) < signal.len() {
+                    signal[n as usize] * w
+                }
+                else {
+                    0.0
+                };
+            }
+            frames.push(self.plan.process_real(&frame));
+        }
+
+        frames
+    }
+
+    /// Turn a list of Rectangular DFT results (as produced by `forward`) back into a signal of
+    /// length `signal_length`, using weighted overlap-add.
+    pub fn inverse(&self, frames: &[(Vec<f64>, Vec<f64>)], signal_length: usize) -> Vec<f64> {
+        let mut output = vec![0f64; signal_length];
+        let mut window_sum = vec![0f64; signal_length];
+
+        for (t, (real, imaginary)) in frames.iter().enumerate() {
+            let frame = self.plan.process_real_inverse(real, imaginary);
+
+            let start = (t * self.hop_size) as isize - (self.frame_size / 2) as isize;
+            for (i, (x, w)) in frame.iter().zip(&self.window).enumerate() {
+                let n = start + i as isize;
+                if n >= 0 && (n as usize) < signal_length {
+                    output[n as usize] += x * w;
+                    window_sum[n as usize] += w * w;
+                }
+            }
+        }
+
+        for n in 0..signal_length {
+            if window_sum[n] > 1e-12 {
+                output[n] /= window_sum[n];
+            }
+        }
+
+        output
+    }
+
+    /// Magnitudes of every frame of the STFT of `signal` (a spectrogram).
+    ///
+    /// The magnitudes are divided by the window's coherent gain, so a sinusoid of amplitude A
+    /// shows up with a peak of about A * frame_size / 2, just like an unwindowed DFT would give.
+    pub fn spectrogram(&self, signal: &[f64]) -> Vec<Vec<f64>> {
+        let gain = self.window.iter().sum::<f64>() / (self.frame_size as f64);
+
+        self.forward(signal)
+            .iter()
+            .map(|(real, imaginary)| {
+                real.iter()
+                    .zip(imaginary.iter())
+                    .map(|(re, im)| re.hypot(*im) / gain)
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64;
+    use dsp::dft::Stft;
+    use dsp::window::Window;
+
+    /// Something with a bit of everything in it.
+    fn test_signal(len: usize) -> Vec<f64> {
+        (0..len).map(|i| (0.37 * i as f64).sin() + ((i * 7919) % 101) as f64 / 50.0 - 1.0).collect()
+    }
+
+    #[test]
+    fn inverse_reconstructs_the_signal() {
+        let signal = test_signal(5000);
+        // (hop size, FFT size) for 512-sample frames, with and without zero-padding
+        for &(hop_size, fft_size) in &[(256, 512), (128, 512), (256, 1024), (100, 2048)] {
+            let stft = Stft::new(512, hop_size, Window::Hann, fft_size);
+            let frames = stft.forward(&signal);
+            assert_eq!(frames.len(), stft.num_frames(signal.len()));
+            assert_eq!(frames[0].0.len(), fft_size / 2 + 1);
+
+            let resynthesized = stft.inverse(&frames, signal.len());
+            for (a, b) in resynthesized.iter().zip(&signal) {
+                assert!((a - b).abs() < 1e-9, "hop {}, FFT size {}", hop_size, fft_size);
+            }
+        }
+    }
+
+    #[test]
+    fn spectrogram_peak_level() {
+        // A sinusoid right on bin 64 of a 1024-sample frame, with amplitude 0.5
+        let signal: Vec<f64> = (0..8192)
+            .map(|n| 0.5 * (2.0 * f64::consts::PI * 64.0 * n as f64 / 1024.0).cos())
+            .collect();
+        for &fft_size in &[1024, 4096] {
+            let stft = Stft::new(1024, 256, Window::Hann, fft_size);
+            let spectrogram = stft.spectrogram(&signal);
+
+            // A frame well inside the signal, at the sinusoid's bin (which moves up with the
+            // zero-padding)
+            let frame = &spectrogram[16];
+            let bin = 64 * fft_size / 1024;
+            assert!((frame[bin] - 0.5 * 1024.0 / 2.0).abs() < 1e-6, "{}", frame[bin]);
+            assert!(frame.iter().all(|&m| m <= frame[bin] + 1e-9));
+        }
+    }
+}
@@ -0,0 +1,413 @@
+//! Segment-based envelope generator.
+
+use std::f64;
+use dsp::traits::Signal;
+use dsp::context::{ProcessContext, DEFAULT_SAMPLE_RATE};
+use dsp::envelopes::{Gate, Trigger};
+
+/// Shape of an envelope segment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Curve {
+    /// Straight line from the start level to the target level
+    Linear,
+    /// Exponential curve that moves quickly at first and then eases into the target level, like a
+    /// capacitor charging (which is how analog envelopes behave). The parameter sets how curved
+    /// it is: around 0.0 is almost a straight line, and 5.0 is a typical analog-style curve.
+    Exponential(f64),
+}
+
+/// One segment of an envelope.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    /// Level the segment ends at
+    pub target: f64,
+    /// How long the segment takes (in seconds)
+    pub time: f64,
+    /// Shape of the segment
+    pub curve: Curve,
+}
+
+impl Segment {
+    /// Creates a new segment.
+    pub fn new(target: f64, time: f64, curve: Curve) -> Segment {
+        Segment {target, time, curve}
+    }
+
+    /// Where the segment is (0.0 = start level, 1.0 = target level) at `position` (0.0 to 1.0)
+    /// through its time.
+    fn shape(&self, position: f64) -> f64 {
+        match self.curve {
+            Curve::Exponential(curvature) if curvature.abs() > 1e-6 => {
+                (1.0 - (-curvature * position).exp()) / (1.0 - (-curvature).exp())
+            },
+            _ => position,
+        }
+    }
+}
+
+// Where the envelope is
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    Idle,             // Finished (or never started), holding the last level
+    Running(usize),   // Moving through a segment
+    Sustaining,       // Holding at the sustain segment's level while the gate is on
+}
+
+/// Envelope generator struct.
+pub struct Envelope {
+    sample_rate: f64,         // Sample rate (for audio playback, etc) - Set by prepare()
+    segments: Vec<Segment>,
+    sustain: Option<usize>,   // Segment to hold at the end of while the gate is on
+    trigger: Trigger,         // What to do when the gate goes on during a note
+    gate: Gate,
+    triggers_seen: usize,     // Gate trigger count at the last sample (to spot new notes)
+    gate_was_on: bool,        // Gate state at the last sample (to spot releases)
+    state: State,
+    start: f64,               // Level the current segment started at
+    position: f64,            // How far through the current segment we are (0.0 to 1.0)
+    level: f64,               // Current level (0.0 to 1.0, before the range is applied)
+    low: f64,                 // Output level for an envelope level of 0.0
+    high: f64,                // Output level for an envelope level of 1.0
+}
+
+impl Envelope {
+    /// Creates a new envelope from a list of segments.
+    ///
+    /// If `sustain` is `Some(i)`, the envelope holds at the end of segment i while the gate is on,
+    /// and runs the rest of the segments when the gate goes off (going straight to them if the
+    /// gate goes off early). If it's `None`, the envelope is "one-shot": it always runs all the way
+    /// through, however long the gate is on. The envelope starts (and rests) at 0.0.
+    pub fn new(segments: Vec<Segment>, sustain: Option<usize>) -> Envelope {
+        if let Some(sustain) = sustain {
+            assert!(sustain < segments.len(), "The sustain segment must be one of the segments");
+        }
+
+        Envelope {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            segments,
+            sustain,
+            trigger: Trigger::Retrigger,
+            gate: Gate::new(),
+            triggers_seen: 0,
+            gate_was_on: false,
+            state: State::Idle,
+            start: 0.0,
+            position: 0.0,
+            level: 0.0,
+            low: 0.0,
+            high: 1.0,
+        }
+    }
+
+    /// Attack-decay-sustain-release envelope with straight-line segments (times in seconds,
+    /// sustain level from 0.0 to 1.0).
+    pub fn adsr(attack: f64, decay: f64, sustain: f64, release: f64) -> Envelope {
+        Envelope::new(vec![Segment::new(1.0, attack, Curve::Linear),
+                           Segment::new(sustain, decay, Curve::Linear),
+                           Segment::new(0.0, release, Curve::Linear)],
+                      Some(1))
+    }
+
+    /// Attack-decay-sustain-release envelope with analog-style exponential segments.
+    pub fn adsr_exponential(attack: f64, decay: f64, sustain: f64, release: f64) -> Envelope {
+        let mut envelope = Envelope::adsr(attack, decay, sustain, release);
+        envelope.set_curve(Curve::Exponential(5.0));
+        envelope
+    }
+
+    /// Attack-release envelope: rises to 1.0, stays there while the gate is on, and falls back to
+    /// 0.0 when it goes off.
+    pub fn ar(attack: f64, release: f64) -> Envelope {
+        Envelope::new(vec![Segment::new(1.0, attack, Curve::Linear),
+                           Segment::new(0.0, release, Curve::Linear)],
+                      Some(0))
+    }
+
+    /// Attack-hold-decay-sustain-release envelope: an ADSR that stays at 1.0 for `hold` seconds
+    /// before starting its decay.
+    pub fn ahdsr(attack: f64, hold: f64, decay: f64, sustain: f64, release: f64) -> Envelope {
+        Envelope::new(vec![Segment::new(1.0, attack, Curve::Linear),
+                           Segment::new(1.0, hold, Curve::Linear),
+                           Segment::new(sustain, decay, Curve::Linear),
+                           Segment::new(0.0, release, Curve::Linear)],
+                      Some(2))
+    }
+
+    /// Gives every segment the same shape.
+    pub fn set_curve(&mut self, curve: Curve) {
+        for segment in &mut self.segments {
+            segment.curve = curve;
+        }
+    }
+
+    /// Chooses what happens when the gate goes on during a note (the default is
+    /// `Trigger::Retrigger`).
+    pub fn set_trigger(&mut self, trigger: Trigger) {
+        self.trigger = trigger;
+    }
+
+    /// Scales the output so it goes from `low` (instead of 0.0) to `high` (instead of 1.0). For
+    /// example, an envelope sweeping a filter cutoff from 200Hz up to 5000Hz and back.
+    pub fn set_range(&mut self, low: f64, high: f64) {
+        self.low = low;
+        self.high = high;
+    }
+
+    /// A handle to the envelope's gate, for starting and ending notes.
+    pub fn gate(&self) -> Gate {
+        self.gate.clone()
+    }
+
+    /// Turns the gate on (same as `gate().on()`).
+    pub fn gate_on(&self) {
+        self.gate.on();
+    }
+
+    /// Turns the gate off (same as `gate().off()`).
+    pub fn gate_off(&self) {
+        self.gate.off();
+    }
+
+    /// Whether the envelope has finished (or hasn't been started yet).
+    pub fn is_idle(&self) -> bool {
+        self.state == State::Idle
+    }
+
+    /// Starts running segment `index` from the current level (or goes idle if there isn't one).
+    fn start_segment(&mut self, index: usize) {
+        self.start = self.level;
+        self.position = 0.0;
+        self.state = if index < self.segments.len() { State::Running(index) } else { State::Idle };
+    }
+
+    /// Reacts to the gate going on or off since the last sample.
+    fn update_gate(&mut self) {
+        let triggers = self.gate.triggers();
+        let gate_on = self.gate.is_on();
+
+        let triggered = triggers != self.triggers_seen;
+        if triggered {
+            // Legato only restarts if the last note was released (or has finished)
+            let held = self.gate_was_on && self.state != State::Idle;
+            if self.trigger == Trigger::Retrigger || !held {
+                self.start_segment(0);
+            }
+            self.triggers_seen = triggers;
+        }
+
+        // (A note can start and end in between two samples, so a new note can need releasing
+        // straight away too)
+        if !gate_on && (self.gate_was_on || triggered) {
+            // Released: skip straight to whatever comes after the sustain segment
+            if let Some(sustain) = self.sustain {
+                let releasing = match self.state {
+                    State::Running(index) => index > sustain,
+                    State::Idle => true,
+                    State::Sustaining => false,
+                };
+                if !releasing {
+                    self.start_segment(sustain + 1);
+                }
+            }
+        }
+
+        self.gate_was_on = gate_on;
+    }
+}
+
+signal_ops!(Envelope);
+
+impl Signal for Envelope {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.sample_rate = context.sample_rate;
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        self.update_gate();
+        let output = self.low + (self.high - self.low) * self.level;
+
+        if let State::Running(index) = self.state {
+            let segment = self.segments[index];
+            let samples = segment.time * self.sample_rate;
+            self.position = if samples >= 1.0 { self.position + 1.0 / samples } else { 1.0 };
+
+            if self.position >= 1.0 {
+                self.level = segment.target;
+                if Some(index) == self.sustain {
+                    self.state = State::Sustaining;
+                }
+                else {
+                    self.start_segment(index + 1);
+                }
+            }
+            else {
+                self.level = self.start + (segment.target - self.start) * segment.shape(self.position);
+            }
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1024 samples per second, so segment times that are whole numbers of samples add up exactly
+    const SAMPLE_RATE: f64 = 1024.0;
+
+    /// Time of `n` samples, in seconds.
+    fn samples(n: usize) -> f64 {
+        n as f64 / SAMPLE_RATE
+    }
+
+    fn prepared(mut envelope: Envelope) -> Envelope {
+        envelope.prepare(&ProcessContext::new(SAMPLE_RATE, 64));
+        envelope
+    }
+
+    /// Checks the next samples of `envelope` against `expected`.
+    fn assert_levels(envelope: &mut Envelope, expected: &[f64]) {
+        for (n, expected) in expected.iter().enumerate() {
+            let level = envelope.evaluate();
+            assert!((level - expected).abs() < 1e-12, "sample {}: {}, expected {}",
+                    n, level, expected);
+        }
+    }
+
+    /// `n` samples of a straight line from `from` towards `to` (stopping one step short of it).
+    fn ramp(from: f64, to: f64, n: usize) -> Vec<f64> {
+        (0..n).map(|i| from + (to - from) * i as f64 / n as f64).collect()
+    }
+
+    #[test]
+    fn adsr_levels_and_timing() {
+        let mut envelope = prepared(Envelope::adsr(samples(8), samples(16), 0.5, samples(4)));
+        assert_levels(&mut envelope, &[0.0; 4]);
+        envelope.gate_on();
+        assert_levels(&mut envelope, &ramp(0.0, 1.0, 8));
+        assert_levels(&mut envelope, &ramp(1.0, 0.5, 16));
+        assert_levels(&mut envelope, &[0.5; 20]);
+        envelope.gate_off();
+        assert_levels(&mut envelope, &ramp(0.5, 0.0, 4));
+        assert_levels(&mut envelope, &[0.0; 4]);
+        assert!(envelope.is_idle());
+    }
+
+    #[test]
+    fn ar_levels_and_timing() {
+        let mut envelope = prepared(Envelope::ar(samples(8), samples(4)));
+        envelope.gate_on();
+        assert_levels(&mut envelope, &ramp(0.0, 1.0, 8));
+        assert_levels(&mut envelope, &[1.0; 20]);
+        envelope.gate_off();
+        assert_levels(&mut envelope, &ramp(1.0, 0.0, 4));
+        assert_levels(&mut envelope, &[0.0; 4]);
+    }
+
+    #[test]
+    fn ahdsr_levels_and_timing() {
+        let mut envelope = prepared(Envelope::ahdsr(samples(8), samples(8), samples(8), 0.25,
+                                                    samples(4)));
+        envelope.gate_on();
+        assert_levels(&mut envelope, &ramp(0.0, 1.0, 8));
+        assert_levels(&mut envelope, &[1.0; 8]);
+        assert_levels(&mut envelope, &ramp(1.0, 0.25, 8));
+        assert_levels(&mut envelope, &[0.25; 20]);
+        envelope.gate_off();
+        assert_levels(&mut envelope, &ramp(0.25, 0.0, 4));
+        assert!(envelope.is_idle());
+    }
+
+    #[test]
+    fn exponential_segments_ease_into_their_targets() {
+        let mut envelope = prepared(Envelope::adsr_exponential(samples(8), samples(8), 0.5,
+                                                               samples(8)));
+        envelope.gate_on();
+        let expected: Vec<f64> = (0..8)
+            .map(|i| (1.0 - (-5.0 * i as f64 / 8.0).exp()) / (1.0 - (-5.0f64).exp()))
+            .collect();
+        assert_levels(&mut envelope, &expected);
+
+        // It still lands on each target on time
+        assert_levels(&mut envelope, &[1.0]);
+        let decay: Vec<f64> = expected.iter().skip(1).map(|x| 1.0 - 0.5 * x).collect();
+        assert_levels(&mut envelope, &decay);
+        assert_levels(&mut envelope, &[0.5; 4]);
+
+        // An (almost) uncurved curve is a straight line
+        let mut envelope = prepared(Envelope::adsr(samples(8), samples(8), 0.5, samples(8)));
+        envelope.set_curve(Curve::Exponential(0.0));
+        envelope.gate_on();
+        assert_levels(&mut envelope, &ramp(0.0, 1.0, 8));
+    }
+
+    #[test]
+    fn retrigger_restarts_from_the_current_level() {
+        let mut envelope = prepared(Envelope::adsr(samples(8), samples(8), 0.5, samples(4)));
+        envelope.gate_on();
+        for _ in 0..20 {
+            envelope.evaluate();
+        }
+        envelope.gate_on();
+        assert_levels(&mut envelope, &ramp(0.5, 1.0, 8));
+        assert_levels(&mut envelope, &ramp(1.0, 0.5, 8));
+    }
+
+    #[test]
+    fn legato_only_restarts_after_a_release() {
+        let mut envelope = prepared(Envelope::adsr(samples(8), samples(8), 0.5, samples(4)));
+        envelope.set_trigger(Trigger::Legato);
+        envelope.gate_on();
+        for _ in 0..20 {
+            envelope.evaluate();
+        }
+        envelope.gate_on();
+        assert_levels(&mut envelope, &[0.5; 8]);
+
+        envelope.gate_off();
+        assert_levels(&mut envelope, &[0.5, 0.375]);
+        envelope.gate_on();
+        assert_levels(&mut envelope, &ramp(0.25, 1.0, 8));
+    }
+
+    #[test]
+    fn early_release_skips_to_the_release() {
+        let mut envelope = prepared(Envelope::adsr(samples(8), samples(8), 0.5, samples(4)));
+        envelope.gate_on();
+        assert_levels(&mut envelope, &ramp(0.0, 0.5, 4));
+        envelope.gate_off();
+        assert_levels(&mut envelope, &ramp(0.5, 0.0, 4));
+        assert_levels(&mut envelope, &[0.0; 4]);
+
+        // A note that starts and ends between two samples still gets released
+        envelope.gate_on();
+        envelope.gate_off();
+        assert_levels(&mut envelope, &[0.0; 8]);
+        assert!(envelope.is_idle());
+    }
+
+    #[test]
+    fn one_shot_envelopes_ignore_the_gate_going_off() {
+        let segments = vec![Segment::new(1.0, samples(8), Curve::Linear),
+                            Segment::new(0.0, samples(8), Curve::Linear)];
+        let mut envelope = prepared(Envelope::new(segments, None));
+        envelope.gate_on();
+        assert_levels(&mut envelope, &[0.0, 0.125]);
+        envelope.gate_off();
+        assert_levels(&mut envelope, &ramp(0.25, 1.0, 6));
+        assert_levels(&mut envelope, &ramp(1.0, 0.0, 8));
+        assert_levels(&mut envelope, &[0.0; 4]);
+        assert!(envelope.is_idle());
+    }
+
+    #[test]
+    fn range_scales_the_output() {
+        let mut envelope = prepared(Envelope::ar(samples(8), samples(4)));
+        envelope.set_range(200.0, 5000.0);
+        assert_levels(&mut envelope, &[200.0; 4]);
+        envelope.gate_on();
+        assert_levels(&mut envelope, &ramp(200.0, 5000.0, 8));
+        assert_levels(&mut envelope, &[5000.0; 4]);
+    }
+}
@@ -0,0 +1,88 @@
+//! Envelope gates.
+//!
+//! Envelopes usually end up buried inside other signals (as the amplitude of a generator, say), so
+//! their gate is controlled through a separate `Gate` handle instead of through the envelope
+//! itself. Handles can be cloned freely and sent to other threads; every clone controls the same
+//! gate.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// What an envelope does when its gate goes on while a note is already playing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Trigger {
+    /// Start the envelope over from the beginning (from its current level, so there's no click)
+    Retrigger,
+    /// Keep going as if nothing happened; only a gate on after the gate has gone off restarts the
+    /// envelope
+    Legato,
+}
+
+struct GateState {
+    on: AtomicBool,        // Whether the gate is currently on
+    triggers: AtomicUsize, // Number of times the gate has gone on (so envelopes can spot new notes)
+}
+
+/// Gate handle struct.
+#[derive(Clone)]
+pub struct Gate {
+    state: Arc<GateState>,
+}
+
+impl Gate {
+    /// Creates a new gate, which starts off.
+    pub fn new() -> Gate {
+        Gate {
+            state: Arc::new(GateState {
+                on: AtomicBool::new(false),
+                triggers: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Turns the gate on (starts a note). Turning it on when it's already on still counts as a
+    /// new note.
+    pub fn on(&self) {
+        self.state.on.store(true, Ordering::SeqCst);
+        self.state.triggers.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Turns the gate off (ends a note).
+    pub fn off(&self) {
+        self.state.on.store(false, Ordering::SeqCst);
+    }
+
+    /// Whether the gate is currently on.
+    pub fn is_on(&self) -> bool {
+        self.state.on.load(Ordering::SeqCst)
+    }
+
+    /// Number of times the gate has gone on so far.
+    pub fn triggers(&self) -> usize {
+        self.state.triggers.load(Ordering::SeqCst)
+    }
+}
+
+impl Default for Gate {
+    fn default() -> Gate {
+        Gate::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_the_gate() {
+        let gate = Gate::new();
+        let handle = gate.clone();
+        assert!(!gate.is_on());
+        handle.on();
+        assert!(gate.is_on());
+        handle.on();
+        gate.off();
+        assert!(!handle.is_on());
+        assert_eq!(gate.triggers(), 2);
+    }
+}
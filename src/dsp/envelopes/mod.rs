@@ -0,0 +1,27 @@
+//! Envelope generators.
+//!
+//! An envelope is a signal that changes shape over the course of a note: it starts moving when
+//! its gate goes on (a key is pressed), and finishes up when its gate goes off (the key is
+//! released). Its output goes from 0.0 to 1.0 by default, so it can be passed straight in as the
+//! amplitude of a generator to give notes a beginning and an end:
+//!
+//! ```ignore
+//! let envelope = Envelope::adsr(0.01, 0.2, 0.6, 0.5);
+//! let gate = envelope.gate();
+//! let note = Saw::new(envelope, 220.0, 0.0);
+//! gate.on();   // ...and later, gate.off()
+//! ```
+//!
+//! Every envelope is a list of segments, each of which moves to a target level over a length of
+//! time. One of the segments can be the "sustain" segment: once it reaches its level, the
+//! envelope holds there until the gate goes off, then moves on to the segments after it (the
+//! release). `Envelope::adsr`, `Envelope::ar` and `Envelope::ahdsr` build the usual shapes, and
+//! `Envelope::new` builds any other one.
+
+// Segment-based envelope generator
+pub mod envelope;
+pub use self::envelope::{Envelope, Segment, Curve};
+
+// Gate handles and trigger modes
+pub mod gate;
+pub use self::gate::{Gate, Trigger};
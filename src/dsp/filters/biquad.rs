@@ -0,0 +1,399 @@
+//! Biquad filter.
+//!
+//! A biquad is a second-order IIR filter: each output sample is a weighted sum of the current and
+//! last two input samples, minus a weighted sum of the last two output samples:
+//!
+//! ```text
+//! y[n] = b0*x[n] + b1*x[n-1] + b2*x[n-2] - a1*y[n-1] - a2*y[n-2]
+//! ```
+//!
+//! With the right five coefficients, that covers all the everyday EQ and synth filter shapes. The
+//! coefficient formulas are the ones from Robert Bristow-Johnson's "Audio EQ Cookbook".
+//!
+//! The cutoff frequency and Q can be constants or other signals. When they change, the
+//! coefficients are recomputed (only when they actually change, so constant settings cost
+//! nothing extra). Biquads can misbehave under very fast, large modulation; the state-variable
+//! filter is a better choice for that.
+
+use std::f64;
+use dsp::traits::Signal;
+use dsp::context::{ProcessContext, DEFAULT_SAMPLE_RATE};
+use dsp::parameter::Parameter;
+use dsp::dft::Complex;
+
+/// Biquad response type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BiquadType {
+    /// Passes frequencies below the cutoff (-12dB/octave above it)
+    Lowpass,
+    /// Passes frequencies above the cutoff (-12dB/octave below it)
+    Highpass,
+    /// Passes frequencies around the cutoff (0dB at the cutoff, bandwidth set by Q)
+    Bandpass,
+    /// Removes frequencies around the cutoff (bandwidth set by Q)
+    Notch,
+    /// Passes every frequency at the same level, but shifts the phase around the cutoff
+    Allpass,
+    /// Boosts or cuts frequencies around the cutoff by the given number of dB
+    Peaking(f64),
+    /// Boosts or cuts frequencies below the cutoff by the given number of dB
+    LowShelf(f64),
+    /// Boosts or cuts frequencies above the cutoff by the given number of dB
+    HighShelf(f64),
+}
+
+/// How the difference equation is computed. Both give the same result (up to rounding), but
+/// behave differently when the coefficients change and in fixed-point-like low-precision cases.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Structure {
+    /// Keeps the last two inputs and outputs. Robust when the coefficients are modulated, since
+    /// the state is just past signal values.
+    DirectFormI,
+    /// Keeps only two state variables. Cheaper, with good floating-point behaviour.
+    TransposedDirectFormII,
+}
+
+/// Biquad coefficients (normalized so a0 is 1.0).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Coefficients {
+    /// Feedforward coefficient for x[n]
+    pub b0: f64,
+    /// Feedforward coefficient for x[n-1]
+    pub b1: f64,
+    /// Feedforward coefficient for x[n-2]
+    pub b2: f64,
+    /// Feedback coefficient for y[n-1]
+    pub a1: f64,
+    /// Feedback coefficient for y[n-2]
+    pub a2: f64,
+}
+
+impl Coefficients {
+    /// Computes the coefficients for a filter type, cutoff frequency (in Hz) and Q at the given
+    /// sample rate.
+    ///
+    /// The cutoff gets limited to just below the Nyquist frequency, and Q to a small positive
+    /// number. A Q of 1/sqrt(2) (about 0.707) gives the flattest possible passband for the low
+    /// and high pass filters, and the steepest slope without a bump for the shelves.
+    pub fn new(filter_type: BiquadType, cutoff: f64, q: f64, sample_rate: f64) -> Coefficients {
+        let cutoff = cutoff.max(1e-3).min(0.4999 * sample_rate);
+        let q = q.max(1e-3);
+
+        let w0 = 2.0 * f64::consts::PI * cutoff / sample_rate;
+        let (sin, cos) = (w0.sin(), w0.cos());
+        let alpha = sin / (2.0 * q);
+
+        let (b0, b1, b2, a0, a1, a2) = match filter_type {
+            BiquadType::Lowpass => {
+                ((1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0,
+                 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+            },
+            BiquadType::Highpass => {
+                ((1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0,
+                 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+            },
+            BiquadType::Bandpass => {
+                (alpha, 0.0, -alpha,
+                 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+            },
+            BiquadType::Notch => {
+                (1.0, -2.0 * cos, 1.0,
+                 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+            },
+            BiquadType::Allpass => {
+                (1.0 - alpha, -2.0 * cos, 1.0 + alpha,
+                 1.0 + alpha, -2.0 * cos, 1.0 - alpha)
+            },
+            BiquadType::Peaking(gain_db) => {
+                let a = 10f64.powf(gain_db / 40.0);
+                (1.0 + alpha * a, -2.0 * cos, 1.0 - alpha * a,
+                 1.0 + alpha / a, -2.0 * cos, 1.0 - alpha / a)
+            },
+            BiquadType::LowShelf(gain_db) => {
+                let a = 10f64.powf(gain_db / 40.0);
+                let k = 2.0 * a.sqrt() * alpha;
+                (a * ((a + 1.0) - (a - 1.0) * cos + k),
+                 2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                 a * ((a + 1.0) - (a - 1.0) * cos - k),
+                 (a + 1.0) + (a - 1.0) * cos + k,
+                 -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                 (a + 1.0) + (a - 1.0) * cos - k)
+            },
+            BiquadType::HighShelf(gain_db) => {
+                let a = 10f64.powf(gain_db / 40.0);
+                let k = 2.0 * a.sqrt() * alpha;
+                (a * ((a + 1.0) + (a - 1.0) * cos + k),
+                 -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                 a * ((a + 1.0) + (a - 1.0) * cos - k),
+                 (a + 1.0) - (a - 1.0) * cos + k,
+                 2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                 (a + 1.0) - (a - 1.0) * cos - k)
+            },
+        };
+
+        Coefficients {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+
+    /// Complex response of the filter at `frequency` (in Hz).
+    pub fn response(&self, frequency: f64, sample_rate: f64) -> Complex {
+        // H(z) = (b0 + b1/z + b2/z^2) / (1 + a1/z + a2/z^2), at z = e^(i*w)
+        let w = 2.0 * f64::consts::PI * frequency / sample_rate;
+        let z1 = Complex::from_angle(-w);
+        let z2 = Complex::from_angle(-2.0 * w);
+        let numerator = Complex::new(self.b0, 0.0) + z1.scale(self.b1) + z2.scale(self.b2);
+        let denominator = Complex::new(1.0, 0.0) + z1.scale(self.a1) + z2.scale(self.a2);
+        numerator / denominator
+    }
+
+    /// Magnitude response (as a gain multiplier) at `num_points` frequencies spaced evenly from
+    /// 0Hz up to (but not including) the Nyquist frequency. Pass it to `graph::plot_vector` to
+    /// plot it.
+    pub fn frequency_response(&self, num_points: usize, sample_rate: f64) -> Vec<f64> {
+        (0..num_points)
+            .map(|i| {
+                let frequency = 0.5 * sample_rate * i as f64 / num_points as f64;
+                self.response(frequency, sample_rate).norm()
+            })
+            .collect()
+    }
+}
+
+/// Biquad filter struct.
+pub struct Biquad {
+    sample_rate: f64,         // Sample rate (for audio playback, etc) - Set by prepare()
+    filtered: Box<Signal>,    // Input signal
+    filter_type: BiquadType,
+    structure: Structure,
+    cutoff: Parameter,        // Cutoff (or center) frequency (in Hz)
+    q: Parameter,             // Q (resonance / bandwidth)
+    coefficients: Coefficients,
+    settings: (f64, f64),     // Cutoff and Q the coefficients were computed for
+    x: [f64; 2],              // Last two inputs (direct form I)
+    y: [f64; 2],              // Last two outputs (direct form I)
+    s: [f64; 2],              // State (transposed direct form II)
+}
+
+impl Biquad {
+    /// Creates a new Biquad filter.
+    ///
+    /// The cutoff and Q can be either an `f64` (a constant value) or another signal, which gets
+    /// evaluated every sample (e.g. an envelope sweeping the cutoff).
+    pub fn new<C, Q>(filtered: Box<Signal>, filter_type: BiquadType, cutoff: C, q: Q) -> Biquad
+        where C: Into<Parameter>, Q: Into<Parameter> {
+        let mut biquad = Biquad {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            filtered,
+            filter_type,
+            structure: Structure::TransposedDirectFormII,
+            cutoff: cutoff.into(),
+            q: q.into(),
+            coefficients: Coefficients::new(filter_type, 1000.0, 1.0, DEFAULT_SAMPLE_RATE),
+            settings: (f64::NAN, f64::NAN),
+            x: [0.0; 2],
+            y: [0.0; 2],
+            s: [0.0; 2],
+        };
+        let (cutoff, q) = (biquad.cutoff.constant(), biquad.q.constant());
+        if let (Some(cutoff), Some(q)) = (cutoff, q) {
+            biquad.update(cutoff, q);
+        }
+        biquad
+    }
+
+    /// Chooses how the filter is computed (the default is `Structure::TransposedDirectFormII`).
+    ///
+    /// The two structures keep different state, so switching clears it and the filter starts
+    /// again from silence.
+    pub fn set_structure(&mut self, structure: Structure) {
+        if structure != self.structure {
+            self.x = [0.0; 2];
+            self.y = [0.0; 2];
+            self.s = [0.0; 2];
+        }
+        self.structure = structure;
+    }
+
+    /// Changes the filter type.
+    pub fn set_filter_type(&mut self, filter_type: BiquadType) {
+        self.filter_type = filter_type;
+        self.settings = (f64::NAN, f64::NAN);
+    }
+
+    /// The current coefficients.
+    pub fn coefficients(&self) -> Coefficients {
+        self.coefficients
+    }
+
+    /// Magnitude response for the current coefficients (see `Coefficients::frequency_response`).
+    pub fn frequency_response(&self, num_points: usize) -> Vec<f64> {
+        self.coefficients.frequency_response(num_points, self.sample_rate)
+    }
+
+    /// Recomputes the coefficients, if the settings have changed.
+    fn update(&mut self, cutoff: f64, q: f64) {
+        if (cutoff, q) != self.settings {
+            self.coefficients = Coefficients::new(self.filter_type, cutoff, q, self.sample_rate);
+            self.settings = (cutoff, q);
+        }
+    }
+
+    /// Filters one sample.
+    fn process(&mut self, input: f64) -> f64 {
+        let c = self.coefficients;
+        match self.structure {
+            Structure::DirectFormI => {
+                let output = c.b0 * input + c.b1 * self.x[0] + c.b2 * self.x[1]
+                    - c.a1 * self.y[0] - c.a2 * self.y[1];
+                self.x = [input, self.x[0]];
+                self.y = [output, self.y[0]];
+                output
+            },
+            Structure::TransposedDirectFormII => {
+                let output = c.b0 * input + self.s[0];
+                self.s[0] = c.b1 * input - c.a1 * output + self.s[1];
+                self.s[1] = c.b2 * input - c.a2 * output;
+                output
+            },
+        }
+    }
+}
+
+signal_ops!(Biquad);
+
+impl Signal for Biquad {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.sample_rate = context.sample_rate;
+        self.filtered.prepare(context);
+        self.cutoff.prepare(context);
+        self.q.prepare(context);
+        self.settings = (f64::NAN, f64::NAN);
+        let (cutoff, q) = (self.cutoff.constant(), self.q.constant());
+        if let (Some(cutoff), Some(q)) = (cutoff, q) {
+            self.update(cutoff, q);
+        }
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let input = self.filtered.evaluate();
+        let cutoff = self.cutoff.evaluate();
+        let q = self.q.evaluate();
+        self.update(cutoff, q);
+        self.process(input)
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        self.filtered.evaluate_block(buffer);
+
+        // Constant settings only need checking once per block
+        if let (Some(cutoff), Some(q)) = (self.cutoff.constant(), self.q.constant()) {
+            self.update(cutoff, q);
+            for sample in buffer.iter_mut() {
+                *sample = self.process(*sample);
+            }
+        }
+        else {
+            for sample in buffer.iter_mut() {
+                let cutoff = self.cutoff.evaluate();
+                let q = self.q.evaluate();
+                self.update(cutoff, q);
+                *sample = self.process(*sample);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dsp::generators::noise::White;
+    use dsp::ops::SignalExt;
+
+    const SAMPLE_RATE: f64 = 44100.0;
+    const CUTOFF: f64 = 1000.0;
+
+    fn gain_db(filter_type: BiquadType, q: f64, frequency: f64) -> f64 {
+        let coefficients = Coefficients::new(filter_type, CUTOFF, q, SAMPLE_RATE);
+        20.0 * coefficients.response(frequency, SAMPLE_RATE).norm().log10()
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!((actual - expected).abs() < tolerance, "{} is not {}", actual, expected);
+    }
+
+    #[test]
+    fn pass_filters_are_3db_down_at_the_cutoff() {
+        let q = f64::consts::FRAC_1_SQRT_2;
+        assert_close(gain_db(BiquadType::Lowpass, q, CUTOFF), -3.01, 0.01);
+        assert_close(gain_db(BiquadType::Highpass, q, CUTOFF), -3.01, 0.01);
+        assert_close(gain_db(BiquadType::Lowpass, q, 0.0), 0.0, 1e-9);
+        assert_close(gain_db(BiquadType::Highpass, q, 0.5 * SAMPLE_RATE), 0.0, 1e-9);
+
+        // In general, the gain at the cutoff is Q
+        assert_close(gain_db(BiquadType::Lowpass, 4.0, CUTOFF), 20.0 * 4f64.log10(), 0.01);
+        assert_close(gain_db(BiquadType::Highpass, 4.0, CUTOFF), 20.0 * 4f64.log10(), 0.01);
+    }
+
+    #[test]
+    fn band_filters_pass_or_remove_the_centre() {
+        assert_close(gain_db(BiquadType::Bandpass, 2.0, CUTOFF), 0.0, 1e-9);
+        assert!(gain_db(BiquadType::Notch, 2.0, CUTOFF) < -120.0);
+        assert_close(gain_db(BiquadType::Notch, 2.0, 0.0), 0.0, 1e-9);
+    }
+
+    #[test]
+    fn allpass_is_flat() {
+        for &frequency in &[0.0, 100.0, CUTOFF, 5000.0, 20000.0] {
+            assert_close(gain_db(BiquadType::Allpass, 2.0, frequency), 0.0, 1e-9);
+        }
+        // The phase is shifted by half a turn at the cutoff
+        let coefficients = Coefficients::new(BiquadType::Allpass, CUTOFF, 2.0, SAMPLE_RATE);
+        let phase = coefficients.response(CUTOFF, SAMPLE_RATE).arg();
+        assert_close(phase.abs(), f64::consts::PI, 1e-9);
+    }
+
+    #[test]
+    fn peaking_and_shelves_reach_their_gain() {
+        let q = f64::consts::FRAC_1_SQRT_2;
+        for &gain in &[6.0, -6.0, 12.0] {
+            assert_close(gain_db(BiquadType::Peaking(gain), q, CUTOFF), gain, 1e-9);
+            // Shelves are halfway (in dB) at the cutoff, and at their full gain at the far end
+            assert_close(gain_db(BiquadType::LowShelf(gain), q, CUTOFF), gain / 2.0, 1e-9);
+            assert_close(gain_db(BiquadType::LowShelf(gain), q, 0.0), gain, 1e-9);
+            assert_close(gain_db(BiquadType::HighShelf(gain), q, CUTOFF), gain / 2.0, 1e-9);
+            assert_close(gain_db(BiquadType::HighShelf(gain), q, 0.5 * SAMPLE_RATE), gain, 1e-9);
+        }
+    }
+
+    #[test]
+    fn switching_structure_starts_from_silence() {
+        let noise = White::new(1.0, 0.0, 7).boxed();
+        let mut switched = Biquad::new(noise, BiquadType::Lowpass, CUTOFF, 2.0);
+        switched.set_structure(Structure::DirectFormI);
+        for _ in 0..100 {
+            switched.evaluate();
+        }
+        switched.set_structure(Structure::TransposedDirectFormII);
+        for _ in 0..100 {
+            switched.evaluate();
+        }
+        switched.set_structure(Structure::DirectFormI);
+
+        // A fresh filter, fed the same noise from the point of the last switch
+        let mut noise = White::new(1.0, 0.0, 7);
+        for _ in 0..200 {
+            noise.evaluate();
+        }
+        let mut fresh = Biquad::new(noise.boxed(), BiquadType::Lowpass, CUTOFF, 2.0);
+        fresh.set_structure(Structure::DirectFormI);
+
+        for _ in 0..100 {
+            assert_eq!(switched.evaluate(), fresh.evaluate());
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! Cascade of second-order sections.
+//!
+//! Runs a filter made of several biquad sections one after the other (each section's output is
+//! the next section's input). This is how the high-order filters from `filters::design` get run.
+
+use dsp::traits::Signal;
+use dsp::context::{ProcessContext, DEFAULT_SAMPLE_RATE};
+use dsp::filters::Coefficients;
+use dsp::filters::design::{self, Prototype, Band};
+
+/// Cascade filter struct.
+pub struct Cascade {
+    sample_rate: f64,                           // Sample rate (for audio playback, etc) - Set by prepare()
+    filtered: Box<Signal>,                      // Input signal
+    specification: Option<(Prototype, usize, Band)>,  // Design to redo when the sample rate changes
+    sections: Vec<Coefficients>,
+    state: Vec<[f64; 2]>,                       // Transposed direct form II state of each section
+}
+
+impl Cascade {
+    /// Creates a new Cascade filter from a filter design (see `filters::design::design`). The
+    /// filter gets redesigned for the right sample rate by `prepare`.
+    pub fn new(filtered: Box<Signal>, prototype: Prototype, order: usize, band: Band) -> Cascade {
+        let mut cascade = Cascade::from_sections(filtered, vec![]);
+        cascade.specification = Some((prototype, order, band));
+        cascade.redesign();
+        cascade
+    }
+
+    /// Creates a new Cascade filter from a list of sections. The sections are only correct for the
+    /// sample rate they were designed for.
+    pub fn from_sections(filtered: Box<Signal>, sections: Vec<Coefficients>) -> Cascade {
+        let state = vec![[0f64; 2]; sections.len()];
+        Cascade {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            filtered,
+            specification: None,
+            sections,
+            state,
+        }
+    }
+
+    /// The filter's sections.
+    pub fn sections(&self) -> &[Coefficients] {
+        &self.sections
+    }
+
+    /// Magnitude response of the whole cascade (see `filters::design::frequency_response`).
+    pub fn frequency_response(&self, num_points: usize) -> Vec<f64> {
+        design::frequency_response(&self.sections, num_points, self.sample_rate)
+    }
+
+    fn redesign(&mut self) {
+        if let Some((prototype, order, band)) = self.specification {
+            self.sections = design::design(prototype, order, band, self.sample_rate);
+            self.state = vec![[0f64; 2]; self.sections.len()];
+        }
+    }
+
+    /// Filters one sample.
+    fn process(&mut self, input: f64) -> f64 {
+        let mut signal = input;
+        for (c, s) in self.sections.iter().zip(self.state.iter_mut()) {
+            let output = c.b0 * signal + s[0];
+            s[0] = c.b1 * signal - c.a1 * output + s[1];
+            s[1] = c.b2 * signal - c.a2 * output;
+            signal = output;
+        }
+        signal
+    }
+}
+
+signal_ops!(Cascade);
+
+impl Signal for Cascade {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.filtered.prepare(context);
+        if context.sample_rate != self.sample_rate {
+            self.sample_rate = context.sample_rate;
+            self.redesign();
+        }
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let input = self.filtered.evaluate();
+        self.process(input)
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        self.filtered.evaluate_block(buffer);
+        for sample in buffer.iter_mut() {
+            *sample = self.process(*sample);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64;
+    use super::*;
+    use dsp::dft::vec_to_polar;
+    use dsp::filters::tests::{Impulse, to_db, max};
+
+    const SAMPLE_RATE: f64 = 44100.0;
+    // One second, so the spectrum has 1Hz bins
+    const LENGTH: usize = 44100;
+
+    fn impulse_response(prototype: Prototype, order: usize, band: Band) -> Vec<f64> {
+        let mut cascade = Cascade::new(Box::new(Impulse::new()), prototype, order, band);
+        cascade.prepare(&ProcessContext::new(SAMPLE_RATE, 64));
+        (0..LENGTH).map(|_| cascade.evaluate()).collect()
+    }
+
+    /// Magnitude response in dB, at 1Hz steps.
+    fn response_db(prototype: Prototype, order: usize, band: Band) -> Vec<f64> {
+        let (magnitude, _) = vec_to_polar(impulse_response(prototype, order, band));
+        to_db(&magnitude)
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!((actual - expected).abs() < tolerance, "{} is not {}", actual, expected);
+    }
+
+    fn min(response: &[f64]) -> f64 {
+        response.iter().cloned().fold(f64::INFINITY, f64::min)
+    }
+
+    const PROTOTYPES: [Prototype; 5] = [
+        Prototype::Butterworth,
+        Prototype::Chebyshev1(1.0),
+        Prototype::Chebyshev2(40.0),
+        Prototype::Bessel,
+        Prototype::Elliptic(1.0, 60.0),
+    ];
+
+    /// Level (in dB) of a prototype at its cutoff.
+    fn edge_level(prototype: Prototype) -> f64 {
+        match prototype {
+            Prototype::Butterworth | Prototype::Bessel => -3.0103,
+            Prototype::Chebyshev1(ripple) | Prototype::Elliptic(ripple, _) => -ripple,
+            Prototype::Chebyshev2(attenuation) => -attenuation,
+        }
+    }
+
+    #[test]
+    fn edges_are_at_the_cutoff_level() {
+        for &prototype in &PROTOTYPES {
+            for order in 3..5 {
+                let level = edge_level(prototype);
+                let lowpass = response_db(prototype, order, Band::Lowpass(1000.0));
+                assert_close(lowpass[1000], level, 0.01);
+                let highpass = response_db(prototype, order, Band::Highpass(1000.0));
+                assert_close(highpass[1000], level, 0.01);
+                let bandpass = response_db(prototype, order, Band::Bandpass(500.0, 2000.0));
+                assert_close(bandpass[500], level, 0.01);
+                assert_close(bandpass[2000], level, 0.01);
+                let bandstop = response_db(prototype, order, Band::Bandstop(500.0, 2000.0));
+                assert_close(bandstop[500], level, 0.01);
+                assert_close(bandstop[2000], level, 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn passbands_and_stopbands() {
+        let response = response_db(Prototype::Butterworth, 4, Band::Lowpass(1000.0));
+        assert!(min(&response[..1001]) > -3.02 && max(&response[..1001]) < 1e-6);
+        assert!(max(&response[2000..]) < -24.0);
+
+        let response = response_db(Prototype::Bessel, 4, Band::Lowpass(1000.0));
+        assert!(min(&response[..1001]) > -3.02 && max(&response[..1001]) < 1e-6);
+        assert!(max(&response[2000..]) < -13.0);
+
+        let response = response_db(Prototype::Chebyshev1(1.0), 4, Band::Lowpass(1000.0));
+        assert!(min(&response[..1001]) > -1.01 && max(&response[..1001]) < 1e-6);
+        assert!(max(&response[2000..]) < -34.0);
+
+        // Chebyshev type II's cutoff is the start of the stopband
+        let response = response_db(Prototype::Chebyshev2(40.0), 4, Band::Lowpass(1000.0));
+        assert!(max(&response[..1001]) < 1e-6 && response[200] > -1.0);
+        assert!(max(&response[1000..]) < -39.99);
+
+        let response = response_db(Prototype::Elliptic(1.0, 60.0), 4, Band::Lowpass(1000.0));
+        assert!(min(&response[..1001]) > -1.01 && max(&response[..1001]) < 1e-6);
+        assert!(max(&response[4000..]) < -59.99);
+    }
+
+    #[test]
+    fn dc_and_nyquist_gain() {
+        let nyquist = LENGTH / 2;
+        for &prototype in &PROTOTYPES {
+            for order in 3..5 {
+                // Even-order equiripple filters start the passband at the bottom of the ripple,
+                // and have a finite stopband (their zeros don't reach DC or Nyquist)
+                let (passband, stopband) = match prototype {
+                    Prototype::Chebyshev1(ripple) if order % 2 == 0 => (-ripple, None),
+                    Prototype::Chebyshev2(attenuation) if order % 2 == 0 => (0.0, Some(-attenuation)),
+                    Prototype::Elliptic(ripple, attenuation) if order % 2 == 0 => {
+                        (-ripple, Some(-attenuation))
+                    },
+                    _ => (0.0, None),
+                };
+                let check_stopband = |level: f64| match stopband {
+                    Some(stopband) => assert_close(level, stopband, 0.01),
+                    None => assert!(level < -200.0),
+                };
+
+                let lowpass = response_db(prototype, order, Band::Lowpass(1000.0));
+                assert_close(lowpass[0], passband, 1e-3);
+                check_stopband(lowpass[nyquist]);
+                let highpass = response_db(prototype, order, Band::Highpass(1000.0));
+                check_stopband(highpass[0]);
+                assert_close(highpass[nyquist], passband, 1e-3);
+                let bandpass = response_db(prototype, order, Band::Bandpass(500.0, 2000.0));
+                check_stopband(bandpass[0]);
+                check_stopband(bandpass[nyquist]);
+                let bandstop = response_db(prototype, order, Band::Bandstop(500.0, 2000.0));
+                assert_close(bandstop[0], passband, 1e-3);
+                assert_close(bandstop[nyquist], passband, 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn stable_up_to_order_8() {
+        let bands = [
+            Band::Lowpass(1000.0),
+            Band::Highpass(10000.0),
+            Band::Bandpass(900.0, 1100.0),
+            Band::Bandstop(900.0, 1100.0),
+        ];
+        for &prototype in &PROTOTYPES {
+            for order in 1..9 {
+                for &band in &bands {
+                    // Every section's poles have to be inside the unit circle (the "stability
+                    // triangle" of a second-order section)
+                    let mut cascade = Cascade::new(Box::new(Impulse::new()), prototype,
+                                                   order, band);
+                    cascade.prepare(&ProcessContext::new(SAMPLE_RATE, 64));
+                    for section in cascade.sections() {
+                        assert!(section.a2 < 1.0 && section.a1.abs() < 1.0 + section.a2,
+                                "{:?}, order {}, {:?} is unstable", prototype, order, band);
+                    }
+
+                    // And the impulse response has to die away (the narrowest ones ring for a
+                    // while)
+                    let response = impulse_response(prototype, order, band);
+                    assert!(response.iter().all(|x| x.is_finite()));
+                    let tail = response[LENGTH - 4410..].iter().fold(0f64, |m, x| m.max(x.abs()));
+                    assert!(tail < 1e-5, "{:?}, order {}, {:?} rings at {}", prototype, order,
+                            band, tail);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn band_edges_have_to_be_in_order() {
+        Cascade::new(Box::new(Impulse::new()), Prototype::Butterworth, 2,
+                     Band::Bandpass(2000.0, 500.0));
+    }
+}
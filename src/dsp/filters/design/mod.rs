@@ -0,0 +1,267 @@
+//! IIR filter design.
+//!
+//! Designs the classic filter families (Butterworth, Chebyshev, Bessel, elliptic) at any order,
+//! as lowpass, highpass, bandpass or bandstop filters. The steps are:
+//!
+//!  1. Build the analog lowpass prototype (see `prototype`)
+//!  2. Move it to the requested band edges (lowpass-to-highpass etc. transformations), with the
+//!     edges "prewarped" so they land in exactly the right place after step 3
+//!  3. Turn the analog filter into a digital one with the bilinear transform
+//!  4. Split the result up into a cascade of second-order sections (biquads), which is much more
+//!     numerically robust than one high-order difference equation
+//!
+//! The sections can be run with `filters::Cascade`, which also redesigns the filter whenever the
+//! sample rate changes.
+
+use std::f64;
+use dsp::dft::Complex;
+use dsp::filters::Coefficients;
+
+// Analog lowpass prototypes
+pub mod prototype;
+pub use self::prototype::{Prototype, analog_prototype};
+
+/// Which frequencies a filter lets through. Frequencies are in Hz.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Band {
+    /// Below the cutoff
+    Lowpass(f64),
+    /// Above the cutoff
+    Highpass(f64),
+    /// Between the low and high edges: `Bandpass(low, high)`
+    Bandpass(f64, f64),
+    /// Everything except between the low and high edges: `Bandstop(low, high)`
+    Bandstop(f64, f64),
+}
+
+/// A filter described by its zeros, poles and gain.
+#[derive(Clone, Debug)]
+pub struct Zpk {
+    /// Zeros of the transfer function
+    pub zeros: Vec<Complex>,
+    /// Poles of the transfer function
+    pub poles: Vec<Complex>,
+    /// Overall gain
+    pub gain: f64,
+}
+
+/// Designs a digital filter, and returns it as second-order sections (to be run one after the
+/// other).
+///
+/// `order` is the order of the lowpass prototype: lowpass and highpass filters end up with that
+/// many poles, and bandpass and bandstop filters with twice as many. Band edges have to be given
+/// low one first.
+pub fn design(prototype: Prototype, order: usize, band: Band, sample_rate: f64) -> Vec<Coefficients> {
+    let analog = analog_prototype(prototype, order);
+
+    // Prewarp the edges: the bilinear transform squashes the whole analog frequency axis into
+    // 0Hz..Nyquist, so ask the analog filter for the frequency that will end up at the right place
+    let nyquist = 0.5 * sample_rate;
+    let warp = |frequency: f64| {
+        let frequency = frequency.max(1e-6).min(0.9999 * nyquist);
+        2.0 * sample_rate * (f64::consts::PI * frequency / sample_rate).tan()
+    };
+    let analog = match band {
+        Band::Lowpass(cutoff) => lowpass_to_lowpass(analog, warp(cutoff)),
+        Band::Highpass(cutoff) => lowpass_to_highpass(analog, warp(cutoff)),
+        Band::Bandpass(low, high) => {
+            assert!(low < high, "The low band edge has to be below the high one");
+            let (low, high) = (warp(low), warp(high));
+            lowpass_to_bandpass(analog, (low * high).sqrt(), high - low)
+        },
+        Band::Bandstop(low, high) => {
+            assert!(low < high, "The low band edge has to be below the high one");
+            let (low, high) = (warp(low), warp(high));
+            lowpass_to_bandstop(analog, (low * high).sqrt(), high - low)
+        },
+    };
+
+    to_sections(&bilinear(analog, sample_rate))
+}
+
+/// Complex response of a cascade of sections at `frequency` (in Hz).
+pub fn response(sections: &[Coefficients], frequency: f64, sample_rate: f64) -> Complex {
+    sections.iter().fold(Complex::new(1.0, 0.0), |product, section| {
+        product * section.response(frequency, sample_rate)
+    })
+}
+
+/// Magnitude response (as a gain multiplier) of a cascade of sections at `num_points`
+/// frequencies spaced evenly from 0Hz up to (but not including) the Nyquist frequency.
+pub fn frequency_response(sections: &[Coefficients], num_points: usize, sample_rate: f64) -> Vec<f64> {
+    (0..num_points)
+        .map(|i| {
+            let frequency = 0.5 * sample_rate * i as f64 / num_points as f64;
+            response(sections, frequency, sample_rate).norm()
+        })
+        .collect()
+}
+
+/// Product of the negated roots (the constant term of the monic polynomial with those roots).
+fn product_of_negated(roots: &[Complex]) -> Complex {
+    roots.iter().fold(Complex::new(1.0, 0.0), |product, root| product * -*root)
+}
+
+/// Moves a 1 radian/second lowpass prototype to a cutoff of `w0` radians/second.
+fn lowpass_to_lowpass(prototype: Zpk, w0: f64) -> Zpk {
+    let degree = prototype.poles.len() - prototype.zeros.len();
+    Zpk {
+        zeros: prototype.zeros.iter().map(|z| z.scale(w0)).collect(),
+        poles: prototype.poles.iter().map(|p| p.scale(w0)).collect(),
+        gain: prototype.gain * w0.powi(degree as i32),
+    }
+}
+
+/// Turns a 1 radian/second lowpass prototype into a highpass with a cutoff of `w0` radians/second
+/// (s -> w0 / s).
+fn lowpass_to_highpass(prototype: Zpk, w0: f64) -> Zpk {
+    let degree = prototype.poles.len() - prototype.zeros.len();
+    let w0 = Complex::new(w0, 0.0);
+    let mut zeros: Vec<Complex> = prototype.zeros.iter().map(|z| w0 / *z).collect();
+    let poles = prototype.poles.iter().map(|p| w0 / *p).collect();
+
+    // The zeros at infinity end up at 0
+    zeros.extend(vec![Complex::new(0.0, 0.0); degree]);
+
+    let gain = prototype.gain * (product_of_negated(&prototype.zeros)
+                                 / product_of_negated(&prototype.poles)).re;
+    Zpk {zeros, poles, gain}
+}
+
+/// Turns a 1 radian/second lowpass prototype into a bandpass centered at `w0` radians/second with
+/// a bandwidth of `bandwidth` radians/second (s -> (s^2 + w0^2) / (s * bandwidth)).
+fn lowpass_to_bandpass(prototype: Zpk, w0: f64, bandwidth: f64) -> Zpk {
+    let degree = prototype.poles.len() - prototype.zeros.len();
+    let split = |roots: &[Complex]| {
+        let mut split = vec![];
+        for root in roots {
+            let scaled = root.scale(bandwidth / 2.0);
+            let offset = (scaled * scaled - Complex::new(w0 * w0, 0.0)).sqrt();
+            split.push(scaled + offset);
+            split.push(scaled - offset);
+        }
+        split
+    };
+    let mut zeros = split(&prototype.zeros);
+    let poles = split(&prototype.poles);
+
+    // Half of the zeros at infinity end up at 0
+    zeros.extend(vec![Complex::new(0.0, 0.0); degree]);
+
+    Zpk {zeros, poles, gain: prototype.gain * bandwidth.powi(degree as i32)}
+}
+
+/// Turns a 1 radian/second lowpass prototype into a bandstop centered at `w0` radians/second with
+/// a bandwidth of `bandwidth` radians/second (s -> (s * bandwidth) / (s^2 + w0^2)).
+fn lowpass_to_bandstop(prototype: Zpk, w0: f64, bandwidth: f64) -> Zpk {
+    let degree = prototype.poles.len() - prototype.zeros.len();
+    let split = |roots: &[Complex]| {
+        let mut split = vec![];
+        for root in roots {
+            let inverted = Complex::new(bandwidth / 2.0, 0.0) / *root;
+            let offset = (inverted * inverted - Complex::new(w0 * w0, 0.0)).sqrt();
+            split.push(inverted + offset);
+            split.push(inverted - offset);
+        }
+        split
+    };
+    let mut zeros = split(&prototype.zeros);
+    let poles = split(&prototype.poles);
+
+    // The zeros at infinity end up at +/- w0 (the middle of the stopband)
+    for _ in 0..degree {
+        zeros.push(Complex::new(0.0, w0));
+        zeros.push(Complex::new(0.0, -w0));
+    }
+
+    let gain = prototype.gain * (product_of_negated(&prototype.zeros)
+                                 / product_of_negated(&prototype.poles)).re;
+    Zpk {zeros, poles, gain}
+}
+
+/// Turns an analog filter into a digital one with the bilinear transform
+/// (s -> 2 * sample_rate * (z - 1) / (z + 1)).
+pub fn bilinear(analog: Zpk, sample_rate: f64) -> Zpk {
+    let degree = analog.poles.len() - analog.zeros.len();
+    let fs2 = Complex::new(2.0 * sample_rate, 0.0);
+    let mut zeros: Vec<Complex> = analog.zeros.iter().map(|z| (fs2 + *z) / (fs2 - *z)).collect();
+    let poles = analog.poles.iter().map(|p| (fs2 + *p) / (fs2 - *p)).collect();
+
+    // Zeros at infinity end up at the Nyquist frequency
+    zeros.extend(vec![Complex::new(-1.0, 0.0); degree]);
+
+    let numerator = analog.zeros.iter().fold(Complex::new(1.0, 0.0), |p, z| p * (fs2 - *z));
+    let denominator = analog.poles.iter().fold(Complex::new(1.0, 0.0), |p, z| p * (fs2 - *z));
+    Zpk {zeros, poles, gain: analog.gain * (numerator / denominator).re}
+}
+
+/// Splits roots up into groups of one or two that each make a real second-order polynomial:
+/// complex conjugate pairs, pairs of real roots, and (for an odd number of real roots) one real
+/// root on its own, which comes last.
+fn group_roots(roots: &[Complex]) -> Vec<Vec<Complex>> {
+    let is_real = |root: &Complex| root.im.abs() <= 1e-9 * (1.0 + root.norm());
+    let mut groups: Vec<Vec<Complex>> = roots.iter()
+        .filter(|root| !is_real(root) && root.im > 0.0)
+        .map(|root| vec![*root, root.conj()])
+        .collect();
+
+    let mut reals: Vec<Complex> = roots.iter()
+        .filter(|root| is_real(root))
+        .map(|root| Complex::new(root.re, 0.0))
+        .collect();
+    reals.sort_by(|a, b| b.re.partial_cmp(&a.re).unwrap());
+    for pair in reals.chunks(2) {
+        groups.push(pair.to_vec());
+    }
+    groups
+}
+
+/// Coefficients [1, c1, c2] of the monic polynomial with one or two roots.
+fn polynomial(group: &[Complex]) -> (f64, f64) {
+    match group.len() {
+        1 => (-group[0].re, 0.0),
+        _ => (-(group[0] + group[1]).re, (group[0] * group[1]).re),
+    }
+}
+
+/// Splits a digital filter up into second-order sections.
+///
+/// Each pair of poles gets the nearest pair of zeros (so their effects mostly cancel out within
+/// the section, which keeps the intermediate signal levels sensible), and the sections are ordered
+/// with the sharpest poles (closest to the unit circle) last. The overall gain goes into the first
+/// section.
+pub fn to_sections(digital: &Zpk) -> Vec<Coefficients> {
+    let mut pole_groups = group_roots(&digital.poles);
+    let mut zero_groups = group_roots(&digital.zeros);
+    let radius = |group: &Vec<Complex>| group.iter().fold(0f64, |r, root| r.max(root.norm()));
+    pole_groups.sort_by(|a, b| radius(b).partial_cmp(&radius(a)).unwrap());
+
+    let mut sections = vec![];
+    for poles in &pole_groups {
+        // Single real poles go with single real zeros if possible, and otherwise the nearest zeros
+        // win
+        let nearest = (0..zero_groups.len()).min_by(|&a, &b| {
+            let cost = |group: &Vec<Complex>| {
+                let size_mismatch = if group.len() != poles.len() { 1e9 } else { 0.0 };
+                size_mismatch + (group[0] - poles[0]).norm()
+            };
+            cost(&zero_groups[a]).partial_cmp(&cost(&zero_groups[b])).unwrap()
+        });
+        let zeros = match nearest {
+            Some(index) => zero_groups.remove(index),
+            None => vec![],
+        };
+
+        let (a1, a2) = polynomial(poles);
+        let (b1, b2) = if zeros.is_empty() { (0.0, 0.0) } else { polynomial(&zeros) };
+        sections.push(Coefficients {b0: 1.0, b1, b2, a1, a2});
+    }
+    sections.reverse();
+
+    if let Some(first) = sections.first_mut() {
+        first.b0 *= digital.gain;
+        first.b1 *= digital.gain;
+        first.b2 *= digital.gain;
+    }
+    sections
+}
@@ -0,0 +1,323 @@
+//! Analog lowpass prototype filters.
+//!
+//! Every classic filter family is designed as an analog lowpass filter with its cutoff at 1
+//! radian/second first (the "prototype"), and only then moved to the requested cutoff, band type
+//! and sample rate. The prototypes are described by their zeros, poles and gain.
+//!
+//! What "cutoff" means depends on the family:
+//!  - Butterworth and Bessel: the -3dB point
+//!  - Chebyshev type I and elliptic: the edge of the passband (where the gain last touches
+//!    -ripple dB)
+//!  - Chebyshev type II: the edge of the stopband (where the gain first reaches -attenuation dB)
+
+use std::f64;
+use dsp::dft::Complex;
+use dsp::filters::design::Zpk;
+
+// Maximum number of Landen transformations (each one squares the modulus, roughly, so this is
+// far more than ever needed):
+const LANDEN_STEPS: usize = 10;
+
+// Number of Durand-Kerner iterations used to find the Bessel poles:
+const ROOT_ITERATIONS: usize = 500;
+
+/// Classic filter family.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Prototype {
+    /// Maximally flat passband, gentle rolloff
+    Butterworth,
+    /// Steeper rolloff, at the cost of ripple (in dB) in the passband
+    Chebyshev1(f64),
+    /// Steeper rolloff with a flat passband, at the cost of a stopband that only goes down to the
+    /// given attenuation (in dB) and then ripples
+    Chebyshev2(f64),
+    /// Gentlest rolloff, but the most constant group delay (preserves the shape of waveforms)
+    Bessel,
+    /// Steepest possible rolloff, with both passband ripple and stopband attenuation (in dB):
+    /// `Elliptic(ripple, attenuation)`
+    Elliptic(f64, f64),
+}
+
+/// Zeros, poles and gain of the analog lowpass prototype of the given order.
+pub fn analog_prototype(prototype: Prototype, order: usize) -> Zpk {
+    assert!(order > 0, "Filters need an order of at least 1");
+    match prototype {
+        Prototype::Butterworth => butterworth(order),
+        Prototype::Chebyshev1(ripple) => chebyshev1(order, ripple),
+        Prototype::Chebyshev2(attenuation) => chebyshev2(order, attenuation),
+        Prototype::Bessel => bessel(order),
+        Prototype::Elliptic(ripple, attenuation) => elliptic(order, ripple, attenuation),
+    }
+}
+
+/// Product of the negated roots (the constant term of the monic polynomial with those roots).
+fn product_of_negated(roots: &[Complex]) -> Complex {
+    roots.iter().fold(Complex::new(1.0, 0.0), |product, root| product * -*root)
+}
+
+/// Gain that makes the zeros and poles give `dc_gain` at 0 radians/second.
+fn gain_for(zeros: &[Complex], poles: &[Complex], dc_gain: f64) -> f64 {
+    dc_gain * (product_of_negated(poles) / product_of_negated(zeros)).re
+}
+
+fn butterworth(order: usize) -> Zpk {
+    // Evenly spaced around the left half of the unit circle
+    let poles = (0..order)
+        .map(|k| {
+            let angle = f64::consts::PI * (2 * k + order + 1) as f64 / (2 * order) as f64;
+            Complex::from_angle(angle)
+        })
+        .collect();
+    Zpk {zeros: vec![], poles, gain: 1.0}
+}
+
+fn chebyshev1(order: usize, ripple: f64) -> Zpk {
+    // Butterworth-like poles, squashed onto an ellipse
+    let epsilon = (10f64.powf(ripple / 10.0) - 1.0).sqrt();
+    let mu = (1.0 / epsilon).asinh() / order as f64;
+    let poles: Vec<Complex> = (0..order)
+        .map(|k| {
+            let theta = f64::consts::PI * (2 * k + 1) as f64 / (2 * order) as f64;
+            Complex::new(-mu.sinh() * theta.sin(), mu.cosh() * theta.cos())
+        })
+        .collect();
+
+    // Odd orders start at 0dB, even orders start at the bottom of the ripple
+    let dc_gain = if order.is_multiple_of(2) { 1.0 / (1.0 + epsilon * epsilon).sqrt() } else { 1.0 };
+    let gain = gain_for(&[], &poles, dc_gain);
+    Zpk {zeros: vec![], poles, gain}
+}
+
+fn chebyshev2(order: usize, attenuation: f64) -> Zpk {
+    // The type I poles (for the matching ripple), inverted, plus zeros on the imaginary axis
+    let epsilon = 1.0 / (10f64.powf(attenuation / 10.0) - 1.0).sqrt();
+    let mu = (1.0 / epsilon).asinh() / order as f64;
+    let mut zeros = vec![];
+    let mut poles = vec![];
+    for k in 0..order {
+        let theta = f64::consts::PI * (2 * k + 1) as f64 / (2 * order) as f64;
+        if 2 * k + 1 != order {
+            zeros.push(Complex::new(0.0, 1.0 / theta.cos()));
+        }
+        let pole = Complex::new(-mu.sinh() * theta.sin(), mu.cosh() * theta.cos());
+        poles.push(Complex::new(1.0, 0.0) / pole);
+    }
+
+    let gain = gain_for(&zeros, &poles, 1.0);
+    Zpk {zeros, poles, gain}
+}
+
+fn bessel(order: usize) -> Zpk {
+    // The poles are the roots of the reverse Bessel polynomial, whose coefficients are
+    // a[k] = (2n - k)! / (2^(n - k) * k! * (n - k)!), with a[n] = 1
+    let mut coefficients = vec![0f64; order + 1];
+    coefficients[order] = 1.0;
+    for k in (0..order).rev() {
+        // (which makes a[k] / a[k + 1] = (2n - k) * (k + 1) / (2 * (n - k)))
+        coefficients[k] = coefficients[k + 1] * ((2 * order - k) * (k + 1)) as f64
+            / (2 * (order - k)) as f64;
+    }
+    let mut poles = polynomial_roots(&coefficients);
+
+    // Scale the poles so the gain is -3dB at 1 radian/second, like the other families
+    let magnitude = |poles: &[Complex], w: f64| {
+        let s = Complex::new(0.0, w);
+        (product_of_negated(poles) / poles.iter().fold(Complex::new(1.0, 0.0), |p, pole| p * (s - *pole))).norm()
+    };
+    let (mut low, mut high) = (0.0, 1.0);
+    while magnitude(&poles, high) > f64::consts::FRAC_1_SQRT_2 {
+        high *= 2.0;
+    }
+    for _ in 0..100 {
+        let middle = 0.5 * (low + high);
+        if magnitude(&poles, middle) > f64::consts::FRAC_1_SQRT_2 { low = middle; } else { high = middle; }
+    }
+    let w3 = 0.5 * (low + high);
+    for pole in &mut poles {
+        *pole = pole.scale(1.0 / w3);
+    }
+
+    let gain = gain_for(&[], &poles, 1.0);
+    Zpk {zeros: vec![], poles, gain}
+}
+
+/// Roots of the polynomial sum(coefficients[k] * s^k), which must be monic (the last coefficient
+/// is 1.0), using the Durand-Kerner method.
+fn polynomial_roots(coefficients: &[f64]) -> Vec<Complex> {
+    let degree = coefficients.len() - 1;
+    let evaluate = |s: Complex| {
+        coefficients.iter().rev().fold(Complex::new(0.0, 0.0), |sum, c| sum * s + Complex::new(*c, 0.0))
+    };
+
+    // Start from points spread around a circle (not on the real axis, so conjugate pairs can
+    // separate)
+    let radius = 1.0 + coefficients.iter().take(degree).fold(0f64, |m, c| m.max(c.abs())).powf(1.0 / degree as f64);
+    let mut roots: Vec<Complex> = (0..degree)
+        .map(|k| Complex::from_angle(0.4 + 2.0 * f64::consts::PI * k as f64 / degree as f64).scale(radius))
+        .collect();
+
+    for _ in 0..ROOT_ITERATIONS {
+        for i in 0..degree {
+            let mut denominator = Complex::new(1.0, 0.0);
+            for j in 0..degree {
+                if i != j {
+                    denominator = denominator * (roots[i] - roots[j]);
+                }
+            }
+            roots[i] = roots[i] - evaluate(roots[i]) / denominator;
+        }
+    }
+
+    // Clean up the rounding error on the real roots
+    for root in &mut roots {
+        if root.im.abs() < 1e-10 * root.norm() {
+            root.im = 0.0;
+        }
+    }
+    roots
+}
+
+fn elliptic(order: usize, ripple: f64, attenuation: f64) -> Zpk {
+    // Follows S. J. Orfanidis, "Lecture Notes on Elliptic Filter Design" (2006): everything is
+    // done with Jacobi elliptic functions computed by Landen transformations.
+    let epsilon_pass = (10f64.powf(ripple / 10.0) - 1.0).sqrt();
+    let epsilon_stop = (10f64.powf(attenuation / 10.0) - 1.0).sqrt();
+    let k1 = epsilon_pass / epsilon_stop;
+    let k = elliptic_degree(order, k1);
+
+    let j = Complex::new(0.0, 1.0);
+    let mut zeros = vec![];
+    let mut poles = vec![];
+    let v0 = (j * -arc_sn(j.scale(1.0 / epsilon_pass), k1)).scale(1.0 / order as f64);
+    for i in 1..(order / 2 + 1) {
+        let u = (2 * i - 1) as f64 / order as f64;
+
+        let zeta = cd(Complex::new(u, 0.0), k).re;
+        let zero = Complex::new(0.0, 1.0 / (k * zeta));
+        zeros.push(zero);
+        zeros.push(zero.conj());
+
+        let pole = j * cd(Complex::new(u, 0.0) - j * v0, k);
+        poles.push(pole);
+        poles.push(pole.conj());
+    }
+    if order % 2 == 1 {
+        let pole = j * sn(j * v0, k);
+        poles.push(Complex::new(pole.re, 0.0));
+    }
+
+    // Odd orders start at 0dB, even orders start at the bottom of the ripple
+    let dc_gain = if order.is_multiple_of(2) { 1.0 / (1.0 + epsilon_pass * epsilon_pass).sqrt() } else { 1.0 };
+    let gain = gain_for(&zeros, &poles, dc_gain);
+    Zpk {zeros, poles, gain}
+}
+
+/// Descending Landen sequence of elliptic moduli, starting from `k`.
+fn landen(k: f64) -> Vec<f64> {
+    let mut moduli = vec![];
+    let mut k = k;
+    for _ in 0..LANDEN_STEPS {
+        if k <= f64::EPSILON {
+            break;
+        }
+        k = (k / (1.0 + (1.0 - k * k).sqrt())).powi(2);
+        moduli.push(k);
+    }
+    moduli
+}
+
+/// Arithmetic-geometric mean.
+fn agm(mut a: f64, mut b: f64) -> f64 {
+    while (a - b).abs() > 1e-15 * a {
+        let next = (0.5 * (a + b), (a * b).sqrt());
+        a = next.0;
+        b = next.1;
+    }
+    a
+}
+
+/// Complete elliptic integral of the first kind, K(k), and of the complementary modulus, K'(k).
+fn elliptic_k(k: f64) -> (f64, f64) {
+    let complement = (1.0 - k * k).sqrt();
+    (f64::consts::FRAC_PI_2 / agm(1.0, complement), f64::consts::FRAC_PI_2 / agm(1.0, k))
+}
+
+/// Solves the degree equation: the modulus k that an elliptic filter of the given order has when
+/// its ripple/attenuation ratio is k1.
+fn elliptic_degree(order: usize, k1: f64) -> f64 {
+    let (k1_k, k1_kp) = elliptic_k(k1);
+    let q = (-f64::consts::PI * k1_kp / k1_k / order as f64).exp();
+    let mut numerator = 1.0;
+    let mut denominator = 1.0;
+    for m in 1..8 {
+        numerator += q.powi(m * (m + 1));
+        denominator += 2.0 * q.powi(m * m);
+    }
+    4.0 * q.sqrt() * (numerator / denominator).powi(2)
+}
+
+fn complex_cos(z: Complex) -> Complex {
+    let j = Complex::new(0.0, 1.0);
+    ((j * z).exp() + (-(j * z)).exp()).scale(0.5)
+}
+
+fn complex_sin(z: Complex) -> Complex {
+    let j = Complex::new(0.0, 1.0);
+    ((j * z).exp() - (-(j * z)).exp()) / Complex::new(0.0, 2.0)
+}
+
+fn complex_acos(z: Complex) -> Complex {
+    let j = Complex::new(0.0, 1.0);
+    let one = Complex::new(1.0, 0.0);
+    -(j * (z + j * (one - z * z).sqrt()).ln())
+}
+
+/// Jacobi elliptic function cd(u*K, k), with u in units of the quarter period K.
+fn cd(u: Complex, k: f64) -> Complex {
+    let mut w = complex_cos(u.scale(f64::consts::FRAC_PI_2));
+    descend(&mut w, k);
+    w
+}
+
+/// Jacobi elliptic function sn(u*K, k), with u in units of the quarter period K.
+fn sn(u: Complex, k: f64) -> Complex {
+    let mut w = complex_sin(u.scale(f64::consts::FRAC_PI_2));
+    descend(&mut w, k);
+    w
+}
+
+/// Ascending Landen transformation shared by `cd` and `sn`.
+fn descend(w: &mut Complex, k: f64) {
+    let one = Complex::new(1.0, 0.0);
+    for v in landen(k).iter().rev() {
+        *w = w.scale(1.0 + v) / (one + (*w * *w).scale(*v));
+    }
+}
+
+/// Inverse of `cd` (the result is in units of the quarter period K).
+fn arc_cd(w: Complex, k: f64) -> Complex {
+    let one = Complex::new(1.0, 0.0);
+    let moduli = landen(k);
+    let mut w = w;
+    let mut previous = k;
+    for v in &moduli {
+        w = w / (one + (one - (w * w).scale(previous * previous)).sqrt()).scale(0.5 * (1.0 + v));
+        previous = *v;
+    }
+    let u = complex_acos(w).scale(2.0 / f64::consts::PI);
+
+    // Reduce to the fundamental period
+    let (k_k, k_kp) = elliptic_k(k);
+    let ratio = k_kp / k_k;
+    Complex::new(symmetric_remainder(u.re, 4.0), symmetric_remainder(u.im, 2.0 * ratio))
+}
+
+/// Inverse of `sn` (the result is in units of the quarter period K).
+fn arc_sn(w: Complex, k: f64) -> Complex {
+    Complex::new(1.0, 0.0) - arc_cd(w, k)
+}
+
+/// Remainder of x / y, in the range -y/2 to y/2.
+fn symmetric_remainder(x: f64, y: f64) -> f64 {
+    x - y * (x / y).round()
+}
@@ -0,0 +1,47 @@
+//! Frequency-sampling FIR design.
+//!
+//! Instead of starting from an ideal impulse response, this method starts from the desired
+//! magnitude response: it's sampled at the kernel's DFT bins, given a linear phase (a delay of
+//! half the kernel length), and turned into a kernel with an inverse DFT. The result matches the
+//! desired response exactly at the bins and ripples in between; a window smooths that out, at the
+//! cost of blurring sharp edges.
+//!
+//! This is the easy way to get arbitrary shapes (tilts, multiple bands, measured curves).
+
+use std::f64;
+use dsp::dft;
+use dsp::window::Window;
+
+/// Designs a linear-phase FIR kernel with `num_taps` taps whose magnitude response follows
+/// `gains`.
+///
+/// `gains` are linear gain multipliers at evenly spaced frequencies from 0Hz up to (and including)
+/// the Nyquist frequency; there have to be at least two of them, and they get linearly
+/// interpolated onto the kernel's DFT bins. An even-length kernel can't have any gain at the
+/// Nyquist frequency, so the last gain is ignored in that case.
+pub fn frequency_sampling(gains: &[f64], num_taps: usize, window: Window) -> Vec<f64> {
+    assert!(gains.len() >= 2);
+    assert!(num_taps > 0);
+
+    let bins = num_taps / 2 + 1;
+    let delay = (num_taps - 1) as f64 / 2.0;
+    let mut magnitude = Vec::with_capacity(bins);
+    let mut phase = Vec::with_capacity(bins);
+    for k in 0..bins {
+        // Where bin k lands on the gains (the last gain is at the Nyquist frequency):
+        let position = (2 * k) as f64 / num_taps as f64 * (gains.len() - 1) as f64;
+        let index = (position.floor() as usize).min(gains.len() - 2);
+        let fraction = position - index as f64;
+        magnitude.push(gains[index] + (gains[index + 1] - gains[index]) * fraction);
+        phase.push(-2.0 * f64::consts::PI * k as f64 * delay / num_taps as f64);
+    }
+    if num_taps.is_multiple_of(2) {
+        magnitude[bins - 1] = 0.0;
+    }
+
+    let mut kernel = dft::polar_to_vec(magnitude, phase, num_taps);
+    for (tap, w) in kernel.iter_mut().zip(window.symmetric(num_taps)) {
+        *tap *= w;
+    }
+    kernel
+}
@@ -0,0 +1,164 @@
+//! FIR (finite impulse response) filters.
+//!
+//! An FIR filter's output is its input convolved with a fixed kernel (the filter's impulse
+//! response). Unlike the IIR filters, FIR filters can't go unstable, and a symmetric kernel has an
+//! exactly linear phase response (every frequency gets delayed by the same half-kernel-length), at
+//! the cost of needing many more taps for the same steepness.
+//!
+//! Kernels can be designed a few different ways:
+//!
+//!  - `windowed_sinc`: the classic method, with the trade-offs controlled by the window
+//!  - `frequency_sampling`: arbitrary magnitude responses
+//!  - `parks_mcclellan`: optimal (equiripple) multi-band designs
+//!
+//! and are run with `Fir`.
+
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+use dsp::dft;
+
+// Windowed-sinc design
+pub mod windowed_sinc;
+pub use self::windowed_sinc::{windowed_sinc, kaiser_parameters};
+
+// Frequency-sampling design
+pub mod frequency_sampling;
+pub use self::frequency_sampling::frequency_sampling;
+
+// Parks-McClellan design
+pub mod remez;
+pub use self::remez::{parks_mcclellan, Specification};
+
+/// Magnitude response (as a gain multiplier) of a kernel at `num_points` frequencies spaced evenly
+/// from 0Hz up to (but not including) the Nyquist frequency.
+pub fn frequency_response(kernel: &[f64], num_points: usize) -> Vec<f64> {
+    // Zero-padding the kernel out to a multiple of 2 * num_points samples puts DFT bins at exactly
+    // those frequencies:
+    let step = kernel.len().div_ceil(2 * num_points).max(1);
+    let mut padded = kernel.to_vec();
+    padded.resize(2 * num_points * step, 0.0);
+    let (magnitude, _) = dft::vec_to_polar(padded);
+    (0..num_points).map(|i| magnitude[i * step]).collect()
+}
+
+/// FIR filter struct.
+pub struct Fir {
+    filtered: Box<Signal>,  // Input signal
+    kernel: Vec<f64>,       // Impulse response, stored backwards (oldest input first)
+    history: Vec<f64>,      // Circular delay line, stored twice over so the newest kernel.len()
+                            // inputs are always one contiguous slice
+    position: usize,        // Where the next input goes in the delay line
+}
+
+impl Fir {
+    /// Creates a new FIR filter that convolves `filtered` with `kernel`.
+    pub fn new(filtered: Box<Signal>, kernel: Vec<f64>) -> Fir {
+        assert!(!kernel.is_empty());
+        let length = kernel.len();
+        Fir {
+            filtered,
+            kernel: kernel.into_iter().rev().collect(),
+            history: vec![0f64; 2 * length],
+            position: 0,
+        }
+    }
+
+    /// The filter's kernel (impulse response).
+    pub fn kernel(&self) -> Vec<f64> {
+        self.kernel.iter().rev().cloned().collect()
+    }
+
+    /// Magnitude response of the filter (see `fir::frequency_response`).
+    pub fn frequency_response(&self, num_points: usize) -> Vec<f64> {
+        frequency_response(&self.kernel(), num_points)
+    }
+
+    /// Filters one sample.
+    fn process(&mut self, input: f64) -> f64 {
+        let length = self.kernel.len();
+        self.history[self.position] = input;
+        self.history[self.position + length] = input;
+        self.position = (self.position + 1) % length;
+
+        // The oldest input is now at `position`, and the newest at `position + length - 1`:
+        let inputs = &self.history[self.position..self.position + length];
+        self.kernel.iter().zip(inputs).map(|(k, x)| k * x).sum()
+    }
+}
+
+signal_ops!(Fir);
+
+impl Signal for Fir {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.filtered.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let input = self.filtered.evaluate();
+        self.process(input)
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        self.filtered.evaluate_block(buffer);
+        for sample in buffer.iter_mut() {
+            *sample = self.process(*sample);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dsp::window::Window;
+    use dsp::filters::design::Band;
+
+    const SAMPLE_RATE: f64 = 48000.0;
+
+    /// A kernel from every design method, with both odd and even lengths.
+    fn kernels() -> Vec<Vec<f64>> {
+        let bands = [
+            Specification::new(0.0, 8000.0, 1.0, 1.0),
+            Specification::new(10000.0, 24000.0, 0.0, 10.0),
+        ];
+        vec![
+            windowed_sinc(Band::Lowpass(10000.0), 101, Window::Hann, SAMPLE_RATE),
+            windowed_sinc(Band::Lowpass(10000.0), 100, Window::Blackman, SAMPLE_RATE),
+            windowed_sinc(Band::Highpass(10000.0), 101, Window::Hamming, SAMPLE_RATE),
+            windowed_sinc(Band::Bandpass(5000.0, 10000.0), 100, Window::Hann, SAMPLE_RATE),
+            windowed_sinc(Band::Bandstop(5000.0, 10000.0), 101, Window::Kaiser(6.0), SAMPLE_RATE),
+            frequency_sampling(&[1.0, 1.0, 0.5, 0.0, 0.0, 0.25, 1.0], 63, Window::Hann),
+            parks_mcclellan(51, &bands, SAMPLE_RATE),
+            parks_mcclellan(52, &bands, SAMPLE_RATE),
+        ]
+    }
+
+    #[test]
+    fn kernels_are_symmetric() {
+        for kernel in kernels() {
+            let length = kernel.len();
+            for n in 0..length {
+                assert!((kernel[n] - kernel[length - 1 - n]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn group_delay_is_half_the_kernel() {
+        for kernel in kernels() {
+            let expected = (kernel.len() - 1) as f64 / 2.0;
+            let mut padded = kernel.clone();
+            padded.resize(4800, 0.0);
+            let (magnitude, phase) = dft::vec_to_polar(padded);
+            let delay = dft::group_delay(phase, 4800);
+
+            // The phase jumps by half a turn at zeros of the response (in the stopbands), so only
+            // look where there's signal
+            for k in 1..magnitude.len() - 1 {
+                if magnitude[k - 1].min(magnitude[k]).min(magnitude[k + 1]) > 0.1 {
+                    assert!((delay[k] - expected).abs() < 1e-6, "{} is not {}", delay[k], expected);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Parks-McClellan (equiripple) FIR design.
+//!
+//! Finds the linear-phase kernel whose largest weighted error (over all the specified bands) is
+//! as small as possible. The error of such a filter ripples evenly across each band, which is
+//! where the name "equiripple" comes from; for a given number of taps it has a narrower
+//! transition band than any windowed design.
+//!
+//! The algorithm is the Remez exchange:
+//!
+//!  1. Guess a set of r + 1 frequencies ("extremals"), where r is the number of free coefficients
+//!  2. Find the polynomial (in cos(w)) whose weighted error is exactly +delta, -delta, +delta...
+//!     at those frequencies (done with barycentric Lagrange interpolation, without ever solving
+//!     for the coefficients)
+//!  3. Move the extremals to where the error of that polynomial actually peaks, and go back to 2
+//!     until the peaks are all the same height
+//!
+//! The kernel is then read off the final response with an inverse DFT.
+
+use std::f64;
+use dsp::dft;
+
+// Grid points per free coefficient (the error gets checked on this grid)
+const GRID_DENSITY: usize = 16;
+
+// Give up after this many exchanges (it normally converges in well under 20)
+const MAX_ITERATIONS: usize = 100;
+
+/// One band of a Parks-McClellan design. Frequencies are in Hz.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Specification {
+    /// Lower edge of the band
+    pub start: f64,
+    /// Upper edge of the band
+    pub end: f64,
+    /// Desired gain (as a multiplier) across the band: 1.0 for a passband, 0.0 for a stopband
+    pub gain: f64,
+    /// How much errors in this band count, relative to the other bands (e.g. a weight of 10 in
+    /// the stopband makes its ripple 10x smaller than the passband's)
+    pub weight: f64,
+}
+
+impl Specification {
+    /// Creates a new band specification.
+    pub fn new(start: f64, end: f64, gain: f64, weight: f64) -> Specification {
+        Specification { start, end, gain, weight }
+    }
+}
+
+/// Designs an equiripple linear-phase FIR kernel with `num_taps` taps.
+///
+/// The bands shouldn't overlap; the gaps between them are "don't care" transition bands. An
+/// even-length kernel always has a zero at the Nyquist frequency, so highpass-like designs need
+/// an odd number of taps.
+pub fn parks_mcclellan(num_taps: usize, bands: &[Specification], sample_rate: f64) -> Vec<f64> {
+    assert!(num_taps > 0);
+    assert!(!bands.is_empty());
+
+    let odd = num_taps % 2 == 1;
+    let r = num_taps.div_ceil(2);
+    let grid = build_grid(r, odd, bands, sample_rate);
+    assert!(grid.len() > r, "the bands are too narrow for this many taps");
+
+    // Start with the extremals spread evenly over the grid:
+    let mut extremals: Vec<usize> = (0..r + 1).map(|i| i * (grid.len() - 1) / r).collect();
+    let mut interpolation = Interpolation::new(&grid, &extremals);
+
+    for _ in 0..MAX_ITERATIONS {
+        let error: Vec<f64> = grid.iter()
+            .map(|point| point.weight * (point.desired - interpolation.evaluate(point.x)))
+            .collect();
+
+        let found = match find_extremals(&error, &grid, r + 1) {
+            Some(found) => found,
+            None => break,
+        };
+
+        // Done once all the error peaks are (nearly) the same height:
+        let (smallest, largest) = found.iter().fold((f64::INFINITY, 0f64), |(s, l), &i| {
+            (s.min(error[i].abs()), l.max(error[i].abs()))
+        });
+        extremals = found;
+        interpolation = Interpolation::new(&grid, &extremals);
+        if largest - smallest <= 1e-6 * largest {
+            break;
+        }
+    }
+
+    // Sample the final response at the kernel's DFT bins, give it a linear phase, and transform
+    // it back:
+    let bins = num_taps / 2 + 1;
+    let delay = (num_taps - 1) as f64 / 2.0;
+    let mut real = Vec::with_capacity(bins);
+    let mut imaginary = Vec::with_capacity(bins);
+    for k in 0..bins {
+        let frequency = k as f64 / num_taps as f64;
+        let mut amplitude = interpolation.evaluate((2.0 * f64::consts::PI * frequency).cos());
+        if !odd {
+            amplitude *= (f64::consts::PI * frequency).cos();
+        }
+        let phase = -2.0 * f64::consts::PI * k as f64 * delay / num_taps as f64;
+        real.push(amplitude * phase.cos());
+        imaginary.push(amplitude * phase.sin());
+    }
+    dft::rectangular_to_vec(real, imaginary, num_taps)
+}
+
+/// A frequency the error gets checked at.
+struct GridPoint {
+    frequency: f64,  // In cycles/sample
+    x: f64,          // cos(2*pi*frequency), which the response is a polynomial in
+    desired: f64,
+    weight: f64,
+    edge: bool,      // Whether this is the first or last point of its band
+}
+
+/// Spreads grid points over the bands.
+///
+/// Even-length kernels have a response of cos(pi*f) * P(cos(2*pi*f)), so they're designed as the
+/// polynomial P with the desired gain divided by cos(pi*f) and the weight multiplied by it. That
+/// blows up at the Nyquist frequency, so the grid stops just short of it.
+fn build_grid(r: usize, odd: bool, bands: &[Specification], sample_rate: f64) -> Vec<GridPoint> {
+    let spacing = 0.5 / (GRID_DENSITY * r) as f64;
+    let mut grid = vec![];
+    for band in bands {
+        let start = (band.start / sample_rate).max(0.0);
+        let mut end = (band.end / sample_rate).min(0.5);
+        if !odd {
+            end = end.min(0.5 - spacing);
+        }
+        if end < start {
+            continue;
+        }
+
+        let points = ((end - start) / spacing).ceil() as usize + 1;
+        for i in 0..points {
+            let frequency = if points == 1 {
+                start
+            }
+            else {
+                start + (end - start) * i as f64 / (points - 1) as f64
+            };
+            let (mut desired, mut weight) = (band.gain, band.weight);
+            if !odd {
+                let c = (f64::consts::PI * frequency).cos();
+                desired /= c;
+                weight *= c;
+            }
+            grid.push(GridPoint {
+                frequency,
+                x: (2.0 * f64::consts::PI * frequency).cos(),
+                desired,
+                weight,
+                edge: i == 0 || i == points - 1,
+            });
+        }
+    }
+    grid.sort_by(|a, b| a.frequency.partial_cmp(&b.frequency).unwrap());
+    grid
+}
+
+/// The polynomial with an alternating weighted error of +/-delta at the extremals, in barycentric
+/// form.
+struct Interpolation {
+    x: Vec<f64>,        // Nodes (the extremals, as cos(2*pi*f))
+    weights: Vec<f64>,  // Barycentric weights of the nodes
+    values: Vec<f64>,   // Value of the polynomial at each node
+}
+
+impl Interpolation {
+    fn new(grid: &[GridPoint], extremals: &[usize]) -> Interpolation {
+        let x: Vec<f64> = extremals.iter().map(|&i| grid[i].x).collect();
+        let n = x.len();
+
+        // Products of many small differences under/overflow quickly, so the factors are doubled
+        // and multiplied in an interleaved order to keep the running product near 1:
+        let step = (n - 1) / 15 + 1;
+        let weights: Vec<f64> = (0..n)
+            .map(|k| {
+                let mut product = 1.0;
+                for start in 0..step {
+                    let mut j = start;
+                    while j < n {
+                        if j != k {
+                            product *= 2.0 * (x[k] - x[j]);
+                        }
+                        j += step;
+                    }
+                }
+                1.0 / product
+            })
+            .collect();
+
+        // Ripple size, from requiring the polynomial's highest coefficient to be zero:
+        let mut numerator = 0.0;
+        let mut denominator = 0.0;
+        let mut sign = 1.0;
+        for (k, &i) in extremals.iter().enumerate() {
+            numerator += weights[k] * grid[i].desired;
+            denominator += sign * weights[k] / grid[i].weight;
+            sign = -sign;
+        }
+        let delta = numerator / denominator;
+
+        let mut sign = 1.0;
+        let values = extremals.iter()
+            .map(|&i| {
+                let value = grid[i].desired - sign * delta / grid[i].weight;
+                sign = -sign;
+                value
+            })
+            .collect();
+
+        Interpolation { x, weights, values }
+    }
+
+    fn evaluate(&self, x: f64) -> f64 {
+        let mut numerator = 0.0;
+        let mut denominator = 0.0;
+        for k in 0..self.x.len() {
+            let difference = x - self.x[k];
+            if difference.abs() < 1e-14 {
+                return self.values[k];
+            }
+            let t = self.weights[k] / difference;
+            numerator += t * self.values[k];
+            denominator += t;
+        }
+        numerator / denominator
+    }
+}
+
+/// Finds `count` alternating peaks of the error, or None if there aren't enough of them.
+fn find_extremals(error: &[f64], grid: &[GridPoint], count: usize) -> Option<Vec<usize>> {
+    // Every local maximum of a positive error and local minimum of a negative error (band edges
+    // only get compared with the neighbour inside their band):
+    let mut found: Vec<usize> = vec![];
+    for i in 0..error.len() {
+        let e = error[i];
+        let previous = if i > 0 && !(grid[i].edge && grid[i - 1].edge) { Some(error[i - 1]) } else { None };
+        let next = if i + 1 < error.len() && !(grid[i].edge && grid[i + 1].edge) { Some(error[i + 1]) } else { None };
+        let peak = if e > 0.0 {
+            previous.is_none_or(|p| e >= p) && next.is_none_or(|n| e > n)
+        }
+        else if e < 0.0 {
+            previous.is_none_or(|p| e <= p) && next.is_none_or(|n| e < n)
+        }
+        else {
+            false
+        };
+        if peak {
+            found.push(i);
+        }
+    }
+
+    let mut found = alternate(found, error);
+    while found.len() > count {
+        if found.len() == count + 1 {
+            // Dropping an end keeps the rest alternating:
+            if error[found[0]].abs() < error[found[found.len() - 1]].abs() {
+                found.remove(0);
+            }
+            else {
+                found.pop();
+            }
+        }
+        else {
+            let smallest = (0..found.len())
+                .min_by(|&a, &b| error[found[a]].abs().partial_cmp(&error[found[b]].abs()).unwrap())
+                .unwrap();
+            found.remove(smallest);
+            found = alternate(found, error);
+        }
+    }
+
+    if found.len() == count { Some(found) } else { None }
+}
+
+/// Merges runs of same-sign peaks, keeping the biggest of each run.
+fn alternate(peaks: Vec<usize>, error: &[f64]) -> Vec<usize> {
+    let mut alternating: Vec<usize> = vec![];
+    for i in peaks {
+        match alternating.last().cloned() {
+            Some(last) if (error[last] > 0.0) == (error[i] > 0.0) => {
+                if error[i].abs() > error[last].abs() {
+                    *alternating.last_mut().unwrap() = i;
+                }
+            },
+            _ => alternating.push(i),
+        }
+    }
+    alternating
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dsp::filters::fir::frequency_response;
+
+    const SAMPLE_RATE: f64 = 48000.0;
+
+    #[test]
+    fn odd_and_even_lengths_are_equiripple() {
+        let bands = [
+            Specification::new(0.0, 8000.0, 1.0, 1.0),
+            Specification::new(10000.0, 24000.0, 0.0, 10.0),
+        ];
+        for &num_taps in &[51, 52] {
+            let kernel = parks_mcclellan(num_taps, &bands, SAMPLE_RATE);
+            assert_eq!(kernel.len(), num_taps);
+
+            // 1Hz steps
+            let response = frequency_response(&kernel, 24000);
+            let passband = response[..8001].iter().fold(0f64, |e, m| e.max((m - 1.0).abs()));
+            let stopband = response[10000..].iter().fold(0f64, |e, m| e.max(*m));
+
+            // The weighted errors come out the same, and the passband touches its largest error
+            // on both sides of 1.0
+            assert!((stopband * 10.0 / passband - 1.0).abs() < 0.01, "{} vs {}", passband, stopband);
+            assert!(passband < 0.03);
+            let lowest = response[..8001].iter().cloned().fold(f64::INFINITY, f64::min);
+            let highest = response[..8001].iter().cloned().fold(0f64, f64::max);
+            assert!((1.0 - lowest - passband).abs() < 0.01 * passband);
+            assert!((highest - 1.0 - passband).abs() < 0.01 * passband);
+        }
+
+        // Even-length kernels have a zero at the Nyquist frequency
+        let kernel = parks_mcclellan(52, &bands, SAMPLE_RATE);
+        let nyquist = kernel.iter().enumerate().fold(0.0, |sum, (n, tap)| {
+            if n % 2 == 0 { sum + tap } else { sum - tap }
+        });
+        assert!(nyquist.abs() < 1e-12);
+    }
+}
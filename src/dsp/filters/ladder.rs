@@ -0,0 +1,218 @@
+//! Ladder filter.
+//!
+//! A model of the Moog transistor ladder: four one-pole lowpass stages in a row (24 dB/octave),
+//! with the output fed back (inverted) to the input to make the resonance. Past full resonance the
+//! feedback is strong enough that the filter oscillates on its own, at the cutoff frequency.
+//!
+//! Like the state-variable filter, each stage is a topology-preserving (trapezoidal) one-pole, and
+//! the feedback loop is solved without a one-sample delay, so the cutoff and resonance can be
+//! modulated quickly. The nonlinear part of the circuit is modelled with a tanh saturator where the
+//! feedback meets the input: the drive pushes the input harder into it (for a thicker, distorted
+//! sound), and it's what keeps the self-oscillation from growing without bound.
+//!
+//! As in the real thing, turning up the resonance thins out the bass.
+
+use std::f64;
+use dsp::traits::Signal;
+use dsp::context::{ProcessContext, DEFAULT_SAMPLE_RATE};
+use dsp::parameter::Parameter;
+
+/// Ladder filter struct.
+pub struct Ladder {
+    sample_rate: f64,       // Sample rate (for audio playback, etc) - Set by prepare()
+    filtered: Box<Signal>,  // Input signal
+    cutoff: Parameter,      // Cutoff frequency (in Hz)
+    resonance: Parameter,   // Resonance, from 0.0 (none); self-oscillates above 1.0
+    drive: Parameter,       // Input gain into the saturator (1.0 is nearly clean for quiet inputs)
+    settings: (f64, f64),   // Cutoff and resonance the coefficients were computed for
+    g: f64,                 // Gain of each stage, g / (1 + g) with g prewarped
+    feedback: f64,          // Feedback amount (4.0 at a resonance of 1.0)
+    s: [f64; 4],            // Stage states
+}
+
+impl Ladder {
+    /// Creates a new Ladder filter.
+    ///
+    /// The resonance goes from 0.0 up to 1.0, where the filter is on the edge of oscillating;
+    /// above that it oscillates on its own (the saturator keeps it from getting louder and
+    /// louder). The cutoff, resonance and drive can be either an `f64` (a constant value) or
+    /// another signal, which gets evaluated every sample.
+    pub fn new<C, R, D>(filtered: Box<Signal>, cutoff: C, resonance: R, drive: D) -> Ladder
+        where C: Into<Parameter>, R: Into<Parameter>, D: Into<Parameter> {
+        let mut ladder = Ladder {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            filtered,
+            cutoff: cutoff.into(),
+            resonance: resonance.into(),
+            drive: drive.into(),
+            settings: (f64::NAN, f64::NAN),
+            g: 0.0,
+            feedback: 0.0,
+            s: [0.0; 4],
+        };
+        ladder.update(1000.0, 0.0);
+        ladder
+    }
+
+    /// Recomputes the coefficients, if the settings have changed.
+    fn update(&mut self, cutoff: f64, resonance: f64) {
+        if (cutoff, resonance) == self.settings {
+            return;
+        }
+        self.settings = (cutoff, resonance);
+
+        let cutoff = cutoff.max(1e-3).min(0.4999 * self.sample_rate);
+        let g = (f64::consts::PI * cutoff / self.sample_rate).tan();
+        self.g = g / (1.0 + g);
+        self.feedback = 4.0 * resonance.max(0.0);
+    }
+
+    /// Filters one sample.
+    fn process(&mut self, input: f64, drive: f64) -> f64 {
+        let g = self.g;
+
+        // Each stage's output is g * (its input) + (1 - g) * s, so the last stage's output is
+        // g^4 * (the ladder's input) plus what the states contribute:
+        let carry = 1.0 - g;
+        let contribution = ((self.s[0] * carry * g + self.s[1] * carry) * g + self.s[2] * carry) * g
+            + self.s[3] * carry;
+
+        // Solve the (linear) feedback loop, then saturate the result:
+        let g4 = g * g * g * g;
+        let mut signal = ((drive * input - self.feedback * contribution) / (1.0 + self.feedback * g4)).tanh();
+
+        for s in self.s.iter_mut() {
+            let v = g * (signal - *s);
+            signal = v + *s;
+            *s = signal + v;
+        }
+        signal
+    }
+}
+
+signal_ops!(Ladder);
+
+impl Signal for Ladder {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.sample_rate = context.sample_rate;
+        self.filtered.prepare(context);
+        self.cutoff.prepare(context);
+        self.resonance.prepare(context);
+        self.drive.prepare(context);
+        let (cutoff, resonance) = self.settings;
+        self.settings = (f64::NAN, f64::NAN);
+        self.update(cutoff, resonance);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let input = self.filtered.evaluate();
+        let cutoff = self.cutoff.evaluate();
+        let resonance = self.resonance.evaluate();
+        self.update(cutoff, resonance);
+        let drive = self.drive.evaluate();
+        self.process(input, drive)
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        self.filtered.evaluate_block(buffer);
+
+        // Constant settings only need checking once per block
+        let (cutoff, resonance) = (self.cutoff.constant(), self.resonance.constant());
+        if let (Some(cutoff), Some(resonance), Some(drive)) = (cutoff, resonance, self.drive.constant()) {
+            self.update(cutoff, resonance);
+            for sample in buffer.iter_mut() {
+                *sample = self.process(*sample, drive);
+            }
+        }
+        else {
+            for sample in buffer.iter_mut() {
+                let cutoff = self.cutoff.evaluate();
+                let resonance = self.resonance.evaluate();
+                self.update(cutoff, resonance);
+                let drive = self.drive.evaluate();
+                *sample = self.process(*sample, drive);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dsp::generators::{Saw, White};
+    use dsp::ops::SignalExt;
+    use dsp::dft::vec_to_polar;
+    use dsp::filters::tests::{Impulse, to_db};
+
+    const SAMPLE_RATE: usize = 44100;
+
+    fn prepared(mut ladder: Ladder) -> Ladder {
+        ladder.prepare(&ProcessContext::new(SAMPLE_RATE as f64, 64));
+        ladder
+    }
+
+    #[test]
+    fn rolls_off_at_24db_per_octave() {
+        // With no resonance (and so no feedback through the saturator), an impulse response is
+        // just the four stages' response, scaled by tanh(drive)
+        let drive = 0.01;
+        let mut ladder = prepared(Ladder::new(Box::new(Impulse::new()), 100.0, 0.0, drive));
+        let mut response = vec![0f64; SAMPLE_RATE];
+        ladder.evaluate_block(&mut response);
+        let (magnitude, _) = vec_to_polar(response);
+        let response: Vec<f64> = to_db(&magnitude).iter()
+            .map(|db| db - 20.0 * drive.tanh().log10())
+            .collect();
+
+        // 3dB down from each stage at the cutoff, and 24dB/octave well above it
+        assert!((response[0]).abs() < 0.01, "{} dB at DC", response[0]);
+        assert!((response[100] + 12.04).abs() < 0.05, "{} dB at the cutoff", response[100]);
+        for &frequency in &[500, 1000, 2000] {
+            let slope = response[frequency] - response[2 * frequency];
+            assert!((slope - 24.0).abs() < 1.0, "{} dB/octave above {}Hz", slope, frequency);
+        }
+    }
+
+    #[test]
+    fn self_oscillates_at_the_cutoff() {
+        // One kick from an impulse is enough to start it; after that it keeps going on its own
+        let mut ladder = prepared(Ladder::new(Box::new(Impulse::new()), 1000.0, 1.2, 1.0));
+        let mut output = vec![0f64; SAMPLE_RATE];
+        ladder.evaluate_block(&mut output);
+
+        // Count the upward zero crossings in the second half (a whole number of cycles long)
+        let settled = &output[SAMPLE_RATE / 2..];
+        let crossings = settled.windows(2).filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0).count();
+        let frequency = crossings as f64 * 2.0;
+        assert!((frequency - 1000.0).abs() < 20.0, "oscillating at {}Hz", frequency);
+        let peak = settled.iter().fold(0f64, |peak, x| peak.max(x.abs()));
+        assert!(peak > 0.1 && peak < 1.5, "oscillating with a peak of {}", peak);
+    }
+
+    #[test]
+    fn audio_rate_cutoff_stays_bounded() {
+        // A new cutoff between 20Hz and 20kHz every sample, with the filter self-oscillating
+        let cutoff = White::new(9990.0, 10010.0, 3);
+        let mut ladder = Ladder::new(Saw::new(1.0, 110.0, 0.0).boxed(), cutoff, 1.2, 2.0);
+        ladder.prepare(&ProcessContext::new(44100.0, 64));
+
+        let mut buffer = vec![0f64; 64];
+        for _ in 0..1000 {
+            ladder.evaluate_block(&mut buffer);
+            assert!(buffer.iter().all(|x| x.is_finite() && x.abs() < 1.5));
+        }
+    }
+
+    #[test]
+    fn constant_settings_match_per_sample_evaluation() {
+        let mut block = Ladder::new(White::new(1.0, 0.0, 5).boxed(), 2000.0, 0.8, 1.5);
+        let mut single = Ladder::new(White::new(1.0, 0.0, 5).boxed(), 2000.0, 0.8, 1.5);
+        let mut buffer = vec![0f64; 64];
+        for _ in 0..10 {
+            block.evaluate_block(&mut buffer);
+            for &sample in &buffer {
+                assert_eq!(sample, single.evaluate());
+            }
+        }
+    }
+}
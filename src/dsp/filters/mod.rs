@@ -0,0 +1,74 @@
+//! Filters.
+//!
+//! Every filter here wraps another signal (its input) and is a signal itself, so filters can be
+//! chained, modulated and mixed like anything else.
+
+// Second-order IIR filters (RBJ cookbook)
+pub mod biquad;
+pub use self::biquad::{Biquad, BiquadType, Coefficients, Structure};
+
+// Classic IIR filter design (Butterworth, Chebyshev, Bessel, elliptic)
+pub mod design;
+
+// Cascades of second-order sections
+pub mod cascade;
+pub use self::cascade::Cascade;
+
+// Synth filters that can be modulated quickly (state-variable and ladder)
+pub mod state_variable;
+pub use self::state_variable::{StateVariable, StateVariableType};
+pub mod ladder;
+pub use self::ladder::Ladder;
+
+// FIR filters and FIR design (windowed-sinc, frequency sampling, Parks-McClellan)
+pub mod fir;
+pub use self::fir::Fir;
+
+/// Response measurements shared by the filters' tests.
+#[cfg(test)]
+pub mod tests {
+    use std::f64;
+    use dsp::traits::Signal;
+    use dsp::context::ProcessContext;
+
+    /// A single 1.0 sample, then silence.
+    pub struct Impulse {
+        done: bool,
+    }
+
+    impl Impulse {
+        /// Creates a new impulse.
+        pub fn new() -> Impulse {
+            Impulse {done: false}
+        }
+    }
+
+    impl Default for Impulse {
+        fn default() -> Impulse {
+            Impulse::new()
+        }
+    }
+
+    signal_ops!(Impulse);
+
+    impl Signal for Impulse {
+        fn prepare(&mut self, _context: &ProcessContext) {}
+
+        fn evaluate(&mut self) -> f64 {
+            let sample = if self.done { 0.0 } else { 1.0 };
+            self.done = true;
+            sample
+        }
+    }
+
+    /// Converts magnitudes (as gain multipliers) to dB, with silence at -400dB rather than
+    /// -infinity.
+    pub fn to_db(magnitude: &[f64]) -> Vec<f64> {
+        magnitude.iter().map(|m| 20.0 * m.max(1e-20).log10()).collect()
+    }
+
+    /// Highest level in a response.
+    pub fn max(response: &[f64]) -> f64 {
+        response.iter().cloned().fold(f64::NEG_INFINITY, f64::max)
+    }
+}
@@ -0,0 +1,268 @@
+//! State-variable filter.
+//!
+//! A two-pole filter built from two integrators in a loop, which gives lowpass, highpass, bandpass
+//! and notch outputs all at once. This is the "topology-preserving transform" version from Vadim
+//! Zavalishin's "The Art of VA Filter Design": each analog integrator is replaced with a
+//! trapezoidal one, and the feedback loop is solved exactly instead of with a one-sample delay.
+//!
+//! Unlike a biquad, the filter's state is kept in the same form as the analog circuit's (the
+//! integrators' outputs), so the cutoff and resonance can be swept as fast as you like (even
+//! every sample, at audio rate) without clicks or blowing up. That makes it the filter to reach
+//! for when an envelope or LFO is driving the cutoff.
+
+use std::f64;
+use dsp::traits::Signal;
+use dsp::context::{ProcessContext, DEFAULT_SAMPLE_RATE};
+use dsp::parameter::Parameter;
+
+/// Which of the state-variable filter's outputs a `StateVariable` produces as a signal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StateVariableType {
+    /// Lowpass (12 dB/octave)
+    Lowpass,
+    /// Highpass (12 dB/octave)
+    Highpass,
+    /// Bandpass (6 dB/octave on each side; the peak gets louder as the resonance goes up)
+    Bandpass,
+    /// Notch (lowpass + highpass)
+    Notch,
+}
+
+/// All the outputs of a state-variable filter for one sample.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Outputs {
+    /// Lowpass output
+    pub lowpass: f64,
+    /// Highpass output
+    pub highpass: f64,
+    /// Bandpass output
+    pub bandpass: f64,
+    /// Notch output
+    pub notch: f64,
+}
+
+/// State-variable filter struct.
+pub struct StateVariable {
+    sample_rate: f64,           // Sample rate (for audio playback, etc) - Set by prepare()
+    filtered: Box<Signal>,      // Input signal
+    filter_type: StateVariableType,
+    cutoff: Parameter,          // Cutoff (or center) frequency (in Hz)
+    resonance: Parameter,       // Resonance, from 0.0 (none) to 1.0 (self-oscillation)
+    settings: (f64, f64),       // Cutoff and resonance the coefficients were computed for
+    damping: f64,               // k = 1/Q
+    a: [f64; 3],                // Coefficients of the solved feedback loop
+    s: [f64; 2],                // Integrator states
+}
+
+impl StateVariable {
+    /// Creates a new StateVariable filter.
+    ///
+    /// The resonance goes from 0.0 (a Q of 0.5, no peak at all) up to 1.0, where the filter rings
+    /// forever. The cutoff and resonance can be either an `f64` (a constant value) or another
+    /// signal, which gets evaluated every sample.
+    pub fn new<C, R>(filtered: Box<Signal>, filter_type: StateVariableType, cutoff: C, resonance: R)
+        -> StateVariable where C: Into<Parameter>, R: Into<Parameter> {
+        let mut filter = StateVariable {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            filtered,
+            filter_type,
+            cutoff: cutoff.into(),
+            resonance: resonance.into(),
+            settings: (f64::NAN, f64::NAN),
+            damping: 2.0,
+            a: [0.0; 3],
+            s: [0.0; 2],
+        };
+        filter.update(1000.0, 0.0);
+        filter
+    }
+
+    /// Changes which output the filter produces as a signal.
+    pub fn set_filter_type(&mut self, filter_type: StateVariableType) {
+        self.filter_type = filter_type;
+    }
+
+    /// Evaluates the next sample, and returns all four outputs for it.
+    pub fn evaluate_all(&mut self) -> Outputs {
+        let input = self.filtered.evaluate();
+        let cutoff = self.cutoff.evaluate();
+        let resonance = self.resonance.evaluate();
+        self.update(cutoff, resonance);
+        self.process(input)
+    }
+
+    /// Recomputes the coefficients, if the settings have changed.
+    fn update(&mut self, cutoff: f64, resonance: f64) {
+        if (cutoff, resonance) == self.settings {
+            return;
+        }
+        self.settings = (cutoff, resonance);
+
+        // Prewarped integrator gain, and damping (2.0 at no resonance, 0.0 at full resonance):
+        let cutoff = cutoff.max(1e-3).min(0.4999 * self.sample_rate);
+        let g = (f64::consts::PI * cutoff / self.sample_rate).tan();
+        let k = 2.0 - 2.0 * resonance.clamp(0.0, 1.0);
+
+        let a1 = 1.0 / (1.0 + g * (g + k));
+        let a2 = g * a1;
+        self.damping = k;
+        self.a = [a1, a2, g * a2];
+    }
+
+    /// Filters one sample.
+    fn process(&mut self, input: f64) -> Outputs {
+        let (a1, a2, a3) = (self.a[0], self.a[1], self.a[2]);
+        let v3 = input - self.s[1];
+        let bandpass = a1 * self.s[0] + a2 * v3;
+        let lowpass = self.s[1] + a2 * self.s[0] + a3 * v3;
+        self.s = [2.0 * bandpass - self.s[0], 2.0 * lowpass - self.s[1]];
+
+        let highpass = input - self.damping * bandpass - lowpass;
+        Outputs { lowpass, highpass, bandpass, notch: lowpass + highpass }
+    }
+
+    /// The output for the filter type.
+    fn select(&self, outputs: Outputs) -> f64 {
+        match self.filter_type {
+            StateVariableType::Lowpass => outputs.lowpass,
+            StateVariableType::Highpass => outputs.highpass,
+            StateVariableType::Bandpass => outputs.bandpass,
+            StateVariableType::Notch => outputs.notch,
+        }
+    }
+}
+
+signal_ops!(StateVariable);
+
+impl Signal for StateVariable {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.sample_rate = context.sample_rate;
+        self.filtered.prepare(context);
+        self.cutoff.prepare(context);
+        self.resonance.prepare(context);
+        let (cutoff, resonance) = self.settings;
+        self.settings = (f64::NAN, f64::NAN);
+        self.update(cutoff, resonance);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let outputs = self.evaluate_all();
+        self.select(outputs)
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        self.filtered.evaluate_block(buffer);
+
+        // Constant settings only need checking once per block
+        if let (Some(cutoff), Some(resonance)) = (self.cutoff.constant(), self.resonance.constant()) {
+            self.update(cutoff, resonance);
+            for sample in buffer.iter_mut() {
+                let outputs = self.process(*sample);
+                *sample = self.select(outputs);
+            }
+        }
+        else {
+            for sample in buffer.iter_mut() {
+                let cutoff = self.cutoff.evaluate();
+                let resonance = self.resonance.evaluate();
+                self.update(cutoff, resonance);
+                let outputs = self.process(*sample);
+                *sample = self.select(outputs);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dsp::generators::{Saw, White};
+    use dsp::ops::SignalExt;
+    use dsp::dft::vec_to_polar;
+    use dsp::filters::tests::{Impulse, to_db, max};
+
+    const SAMPLE_RATE: f64 = 44100.0;
+
+    /// Magnitude response in dB at 1Hz steps, from one second of impulse response.
+    fn response_db(filter_type: StateVariableType, cutoff: f64, resonance: f64) -> Vec<f64> {
+        let impulse = Box::new(Impulse::new());
+        let mut filter = StateVariable::new(impulse, filter_type, cutoff, resonance);
+        filter.prepare(&ProcessContext::new(SAMPLE_RATE, 64));
+        let mut response = vec![0f64; SAMPLE_RATE as usize];
+        filter.evaluate_block(&mut response);
+        let (magnitude, _) = vec_to_polar(response);
+        to_db(&magnitude)
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!((actual - expected).abs() < tolerance, "{} is not {}", actual, expected);
+    }
+
+    // A Q of 1/sqrt(2) (k = sqrt(2)) puts the lowpass and highpass outputs 3dB down at the cutoff
+    fn butterworth() -> f64 {
+        1.0 - f64::consts::FRAC_1_SQRT_2
+    }
+
+    #[test]
+    fn lowpass_response() {
+        let response = response_db(StateVariableType::Lowpass, 1000.0, butterworth());
+        assert_close(response[0], 0.0, 0.01);
+        assert_close(response[1000], -3.01, 0.05);
+        assert!(response[10000] < -39.0, "{} dB a decade up", response[10000]);
+        assert!(max(&response) < 0.01);
+    }
+
+    #[test]
+    fn highpass_response() {
+        let response = response_db(StateVariableType::Highpass, 1000.0, butterworth());
+        assert_close(response[22050], 0.0, 0.01);
+        assert_close(response[1000], -3.01, 0.05);
+        assert!(response[100] < -39.0, "{} dB a decade down", response[100]);
+        assert!(max(&response) < 0.01);
+    }
+
+    #[test]
+    fn bandpass_response() {
+        // The peak is at the cutoff, at a gain of Q = 1/k
+        for &resonance in &[butterworth(), 0.5, 0.9] {
+            let response = response_db(StateVariableType::Bandpass, 1000.0, resonance);
+            let peak = -20.0 * (2.0 - 2.0 * resonance).log10();
+            assert_close(response[1000], peak, 0.01);
+            assert_close(max(&response), peak, 0.01);
+            // (6dB/octave on either side, whatever the Q)
+            assert!(response[100] < -19.0 && response[10000] < -19.0);
+        }
+    }
+
+    #[test]
+    fn notch_response() {
+        let response = response_db(StateVariableType::Notch, 1000.0, butterworth());
+        assert_close(response[0], 0.0, 0.01);
+        assert_close(response[22050], 0.0, 0.01);
+        assert!(response[1000] < -80.0, "{} dB at the notch", response[1000]);
+        assert!(max(&response) < 0.01);
+    }
+
+    #[test]
+    fn audio_rate_cutoff_stays_bounded() {
+        // A new cutoff between 20Hz and 20kHz every sample, with plenty of resonance
+        let types = [
+            StateVariableType::Lowpass,
+            StateVariableType::Highpass,
+            StateVariableType::Bandpass,
+            StateVariableType::Notch,
+        ];
+        for &filter_type in &types {
+            let cutoff = White::new(9990.0, 10010.0, 3);
+            let input = Saw::new(1.0, 110.0, 0.0).boxed();
+            let mut filter = StateVariable::new(input, filter_type, cutoff, 0.95);
+            filter.prepare(&ProcessContext::new(44100.0, 64));
+
+            let mut buffer = vec![0f64; 64];
+            for _ in 0..1000 {
+                filter.evaluate_block(&mut buffer);
+                assert!(buffer.iter().all(|x| x.is_finite() && x.abs() < 10.0));
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Signal that scales another signal by a gain
+//!
+//! The gain can be a constant or another signal (e.g. an envelope or a volume control), and can
+//! be given either as a plain multiplier or in decibels. A gain of +6dB is roughly 2x, -6dB is
+//! roughly 0.5x, and 0dB is 1x (no change).
+
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+use dsp::parameter::Parameter;
+
+/// Converts a level in decibels to a linear gain multiplier.
+pub fn db_to_linear(db: f64) -> f64 {
+    10f64.powf(db / 20.0)
+}
+
+/// Converts a linear gain multiplier to a level in decibels (a gain of 0.0 is -infinity dB).
+pub fn linear_to_db(gain: f64) -> f64 {
+    20.0 * gain.abs().log10()
+}
+
+/// Gain struct
+pub struct Gain {
+    scaled: Box<Signal>,
+    gain: Parameter,   // Gain (a multiplier, or in dB if `decibels` is set)
+    decibels: bool,    // Whether the gain is in dB
+}
+
+impl Gain {
+    /// Creates a new Gain signal, with the gain as a plain multiplier
+    pub fn new<G: Into<Parameter>>(scaled: Box<Signal>, gain: G) -> Gain {
+        Gain {scaled, gain: gain.into(), decibels: false}
+    }
+
+    /// Creates a new Gain signal, with the gain in decibels
+    pub fn new_db<G: Into<Parameter>>(scaled: Box<Signal>, gain_db: G) -> Gain {
+        Gain {scaled, gain: gain_db.into(), decibels: true}
+    }
+
+    /// Sets a new gain (as a plain multiplier)
+    pub fn set_gain<G: Into<Parameter>>(&mut self, gain: G) {
+        self.gain = gain.into();
+        self.decibels = false;
+    }
+
+    /// Sets a new gain (in decibels)
+    pub fn set_gain_db<G: Into<Parameter>>(&mut self, gain_db: G) {
+        self.gain = gain_db.into();
+        self.decibels = true;
+    }
+
+    /// Gain multiplier for the next sample
+    fn next_gain(&mut self) -> f64 {
+        let gain = self.gain.evaluate();
+        if self.decibels { db_to_linear(gain) } else { gain }
+    }
+}
+
+signal_ops!(Gain);
+
+impl Signal for Gain {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.scaled.prepare(context);
+        self.gain.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        self.scaled.evaluate() * self.next_gain()
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        self.scaled.evaluate_block(buffer);
+
+        // A constant gain (especially in dB) only needs converting once per block
+        let constant = self.gain.constant().map(|gain| {
+            if self.decibels { db_to_linear(gain) } else { gain }
+        });
+        for sample in buffer.iter_mut() {
+            *sample *= match constant {
+                Some(gain) => gain,
+                None => self.next_gain(),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dsp::generators::{Saw, Sine};
+
+    #[test]
+    fn db_round_trip() {
+        for &db in &[-60.0, -6.0, 0.0, 3.0, 20.0] {
+            assert!((linear_to_db(db_to_linear(db)) - db).abs() < 1e-12);
+        }
+        assert!((db_to_linear(-20.0) - 0.1).abs() < 1e-12);
+        assert!((linear_to_db(-2.0) - linear_to_db(2.0)).abs() < 1e-12);
+        assert_eq!(linear_to_db(0.0), f64::NEG_INFINITY);
+    }
+
+    /// Checks that two identical gains give the same output by block as by sample.
+    fn assert_block_matches_evaluate(mut by_sample: Gain, mut by_block: Gain) {
+        let mut buffer = vec![0f64; 256];
+        by_block.evaluate_block(&mut buffer);
+        for &sample in &buffer {
+            assert!((sample - by_sample.evaluate()).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn db_gain_block_matches_evaluate() {
+        // A constant gain (converted once per block) and a modulated one (converted every sample)
+        let constant = || Gain::new_db(Box::new(Saw::new(1.0, 441.0, 0.0)), -6.0);
+        assert_block_matches_evaluate(constant(), constant());
+        let modulated = || Gain::new_db(Box::new(Saw::new(1.0, 441.0, 0.0)),
+                                        Sine::new(12.0, 5.0, -6.0));
+        assert_block_matches_evaluate(modulated(), modulated());
+
+        let mut saw = Saw::new(1.0, 441.0, 0.0);
+        let mut buffer = vec![0f64; 256];
+        constant().evaluate_block(&mut buffer);
+        for &sample in &buffer {
+            assert!((sample - saw.evaluate() * db_to_linear(-6.0)).abs() < 1e-12);
+        }
+    }
+}
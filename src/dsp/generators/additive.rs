@@ -0,0 +1,258 @@
+//! Additive synthesis signal generator.
+//!
+//! Additive synthesis builds a sound directly out of its frequency-domain description: a list of
+//! sine waves ("partials"), each with its own frequency ratio, amplitude and phase. Partials with
+//! whole-number ratios (1, 2, 3, ...) are harmonics, and give a pitched, periodic sound; other
+//! ratios give inharmonic sounds like bells.
+//!
+//! Any partial whose frequency is at or above the Nyquist frequency is skipped (it would only
+//! alias), so additive waveforms are perfectly band-limited, at the cost of one sine per partial
+//! per sample.
+//!
+//! The `saw`, `square` and `triangle` presets build the same waveforms as the `Saw`, `Square` and
+//! `Triangle` generators out of their Fourier series, which makes them a handy band-limited
+//! reference to compare the time-domain generators against. (Like any truncated Fourier series,
+//! the saw and square presets overshoot by about 9% next to each jump - the Gibbs phenomenon.)
+
+use dsp::traits::{Signal, Oscillator};
+use dsp::context::{ProcessContext, DEFAULT_SAMPLE_RATE};
+use dsp::parameter::Parameter;
+use std::f64;
+
+/// One sine wave component of an additive sound.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Partial {
+    /// Frequency of the partial, as a multiple of the generator's frequency
+    pub ratio: f64,
+    /// Amplitude of the partial (relative to the generator's amplitude)
+    pub amplitude: f64,
+    /// Phase offset of the partial (as a percent of the partial's whole period)
+    pub phase: f64,
+}
+
+impl Partial {
+    /// Creates a new partial.
+    pub fn new(ratio: f64, amplitude: f64, phase: f64) -> Partial {
+        Partial {ratio, amplitude, phase}
+    }
+}
+
+/// Additive generator struct.
+pub struct Additive {
+    sample_rate: f64,        // Sample rate (for audio playback, etc) - Set by prepare()
+    amplitude: Parameter,    // Amplitude of the Additive wave
+    frequency: Parameter,    // Frequency of the Additive wave (in Hz)
+    offset: Parameter,       // DC offset of the Additive wave    (+/- y axis)
+    partials: Vec<Partial>,  // The sine waves to add up
+    phases: Vec<f64>,        // Current phase of each partial (as a percent of its whole period)
+    initial_phase: f64,      // Phase the generator starts at (and goes back to on reset)
+}
+
+impl Additive {
+    /// Creates a new Additive signal generator from a list of partials.
+    ///
+    /// Each parameter can be either an `f64` (a constant value) or another signal, which gets
+    /// evaluated every sample (e.g. an LFO for vibrato or tremolo).
+    pub fn new<A, F, O>(amplitude: A, frequency: F, offset: O, partials: Vec<Partial>) -> Additive
+        where A: Into<Parameter>, F: Into<Parameter>, O: Into<Parameter> {
+        let phases = vec![0f64; partials.len()];
+        Additive {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            amplitude: amplitude.into(),
+            frequency: frequency.into(),
+            offset: offset.into(),
+            partials,
+            phases,
+            initial_phase: 0.0,
+        }
+    }
+
+    /// Saw wave made of the first `harmonics` harmonics (harmonic x has an amplitude of 1/x).
+    pub fn saw<A, F, O>(amplitude: A, frequency: F, offset: O, harmonics: usize) -> Additive
+        where A: Into<Parameter>, F: Into<Parameter>, O: Into<Parameter> {
+        // Ramping up from -1.0 to 1.0 is -(2/pi) * sum(sin(2*pi*x*phase) / x)
+        let partials = (1..(harmonics + 1))
+            .map(|x| Partial::new(x as f64, -2.0 / (f64::consts::PI * x as f64), 0.0))
+            .collect();
+        Additive::new(amplitude, frequency, offset, partials)
+    }
+
+    /// Square wave made of the first `harmonics` harmonics (only the odd ones are non-zero, and
+    /// harmonic x has an amplitude of 1/x).
+    pub fn square<A, F, O>(amplitude: A, frequency: F, offset: O, harmonics: usize) -> Additive
+        where A: Into<Parameter>, F: Into<Parameter>, O: Into<Parameter> {
+        // -1.0 for the first half of the period and 1.0 for the second half is
+        // -(4/pi) * sum(sin(2*pi*x*phase) / x) over the odd harmonics
+        let partials = (1..(harmonics + 1))
+            .filter(|x| x % 2 == 1)
+            .map(|x| Partial::new(x as f64, -4.0 / (f64::consts::PI * x as f64), 0.0))
+            .collect();
+        Additive::new(amplitude, frequency, offset, partials)
+    }
+
+    /// Triangle wave made of the first `harmonics` harmonics (only the odd ones are non-zero, and
+    /// harmonic x has an amplitude of 1/x^2).
+    pub fn triangle<A, F, O>(amplitude: A, frequency: F, offset: O, harmonics: usize) -> Additive
+        where A: Into<Parameter>, F: Into<Parameter>, O: Into<Parameter> {
+        // Starting at -1.0 and peaking at 1.0 halfway through is
+        // -(8/pi^2) * sum(cos(2*pi*x*phase) / x^2) over the odd harmonics (and a cosine is a sine
+        // a quarter of a period ahead)
+        let partials = (1..(harmonics + 1))
+            .filter(|x| x % 2 == 1)
+            .map(|x| {
+                let amplitude = -8.0 / (f64::consts::PI * f64::consts::PI * (x * x) as f64);
+                Partial::new(x as f64, amplitude, 0.25)
+            })
+            .collect();
+        Additive::new(amplitude, frequency, offset, partials)
+    }
+
+    /// The partials making up the sound.
+    pub fn partials(&self) -> &[Partial] {
+        &self.partials
+    }
+
+    /// Sum of the partials below the Nyquist frequency, with each partial's phase given by
+    /// `phase(i)`, for a phase increment per sample of `increment`.
+    fn waveform<P: Fn(usize) -> f64>(&self, phase: P, increment: f64) -> f64 {
+        let mut output = 0.0;
+        for (i, partial) in self.partials.iter().enumerate() {
+            if (partial.ratio * increment).abs() >= 0.5 {
+                continue;
+            }
+            output += partial.amplitude * (2.0 * f64::consts::PI * (phase(i) + partial.phase)).sin();
+        }
+        output
+    }
+
+    /// Moves every partial's phase forward by `increment` (scaled by its ratio).
+    fn advance(&mut self, increment: f64) {
+        for (phase, partial) in self.phases.iter_mut().zip(self.partials.iter()) {
+            *phase = (*phase + partial.ratio * increment).fract();
+        }
+    }
+}
+
+signal_ops!(Additive);
+
+impl Signal for Additive {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.sample_rate = context.sample_rate;
+        self.amplitude.prepare(context);
+        self.frequency.prepare(context);
+        self.offset.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let increment = self.frequency.evaluate() / self.sample_rate;
+        let mut output = {
+            let phases = &self.phases;
+            self.waveform(|i| phases[i], increment)
+        };
+        self.advance(increment);
+
+        // Transform the signal, taking into account the amplitude and DC offset
+        output *= self.amplitude.evaluate();
+        output += self.offset.evaluate();
+
+        // Return the output
+        output
+    }
+}
+
+impl Oscillator for Additive {
+    fn set_phase(&mut self, phase: f64) {
+        self.initial_phase = phase - phase.floor();
+        self.reset();
+    }
+
+    fn reset(&mut self) {
+        for (phase, partial) in self.phases.iter_mut().zip(self.partials.iter()) {
+            *phase = (self.initial_phase * partial.ratio).fract();
+        }
+    }
+
+    fn evaluate_synced(&mut self, fraction: f64) -> (f64, f64) {
+        let increment = self.frequency.evaluate() / self.sample_rate;
+        let amplitude = self.amplitude.evaluate();
+        let (output, before, after) = {
+            let phases = &self.phases;
+            let partials = &self.partials;
+            let initial_phase = self.initial_phase;
+            (self.waveform(|i| phases[i], increment),
+             self.waveform(|i| phases[i] + fraction * increment * partials[i].ratio, increment),
+             self.waveform(|i| initial_phase * partials[i].ratio, increment))
+        };
+        let output = output * amplitude + self.offset.evaluate();
+
+        // Run up to the reset, then jump back to the initial phase for the rest of the period
+        self.reset();
+        self.advance((1.0 - fraction) * increment);
+
+        (output, (after - before) * amplitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dsp::context::ProcessContext;
+    use dsp::dft::vec_to_polar;
+    use dsp::generators::{Additive, Antialiasing, Saw, Square, Triangle};
+    use dsp::traits::Signal;
+
+    // One second at 44.1kHz (so every DFT bin is 1Hz wide) of a 105Hz wave, whose harmonics all
+    // land exactly on bins. Its period is a whole (even) number of samples, so the naive square
+    // and triangle waves are exactly symmetric, without any even harmonics.
+    const SAMPLE_RATE: usize = 44100;
+    const FREQUENCY: usize = 105;
+    const HARMONICS: usize = 20;
+
+    /// Magnitudes of the first `HARMONICS` harmonics of the signal.
+    fn harmonics(signal: &mut Signal) -> Vec<f64> {
+        signal.prepare(&ProcessContext::new(SAMPLE_RATE as f64, SAMPLE_RATE));
+        let mut samples = vec![0f64; SAMPLE_RATE];
+        signal.evaluate_block(&mut samples);
+        let (magnitude, _) = vec_to_polar(samples);
+        (1..(HARMONICS + 1)).map(|x| magnitude[x * FREQUENCY]).collect()
+    }
+
+    /// Checks that the preset's harmonics match the naive waveform's (to within 1%, since the
+    /// naive waveform's aliases land on the harmonics too).
+    fn assert_same_harmonics(preset: &mut Signal, naive: &mut Signal) {
+        let preset = harmonics(preset);
+        let naive = harmonics(naive);
+        let fundamental = naive[0];
+        for (x, (p, n)) in preset.iter().zip(naive.iter()).enumerate() {
+            if n / fundamental < 1e-3 {
+                assert!(p / fundamental < 1e-3, "harmonic {}: {} (should be missing)", x + 1, p);
+            }
+            else {
+                assert!((p / n - 1.0).abs() < 0.01, "harmonic {}: {} vs naive {}", x + 1, p, n);
+            }
+        }
+    }
+
+    #[test]
+    fn saw_matches_naive_saw() {
+        let mut naive = Saw::new(1.0, FREQUENCY as f64, 0.0);
+        naive.set_antialiasing(Antialiasing::Naive);
+        let mut preset = Additive::saw(1.0, FREQUENCY as f64, 0.0, 200);
+        assert_same_harmonics(&mut preset, &mut naive);
+    }
+
+    #[test]
+    fn square_matches_naive_square() {
+        let mut naive = Square::new(1.0, FREQUENCY as f64, 0.0);
+        naive.set_antialiasing(Antialiasing::Naive);
+        let mut preset = Additive::square(1.0, FREQUENCY as f64, 0.0, 200);
+        assert_same_harmonics(&mut preset, &mut naive);
+    }
+
+    #[test]
+    fn triangle_matches_naive_triangle() {
+        let mut naive = Triangle::new(1.0, FREQUENCY as f64, 0.0);
+        naive.set_antialiasing(Antialiasing::Naive);
+        let mut preset = Additive::triangle(1.0, FREQUENCY as f64, 0.0, 200);
+        assert_same_harmonics(&mut preset, &mut naive);
+    }
+}
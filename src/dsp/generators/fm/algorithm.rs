@@ -0,0 +1,99 @@
+//! FM algorithms (operator routing).
+//!
+//! An algorithm is a matrix saying how strongly each operator modulates every other operator
+//! (including itself, which is feedback), plus the level each operator is mixed into the final
+//! output at. Operators that are heard are "carriers", and operators that only modulate others are
+//! "modulators".
+
+/// Algorithm struct.
+#[derive(Clone, Debug)]
+pub struct Algorithm {
+    num_operators: usize,
+    modulation: Vec<f64>,  // modulation[from * num_operators + to]
+    outputs: Vec<f64>,     // Output level of each operator
+}
+
+impl Algorithm {
+    /// Creates a new algorithm for `num_operators` operators, with no connections at all (and so
+    /// no output).
+    pub fn new(num_operators: usize) -> Algorithm {
+        Algorithm {
+            num_operators,
+            modulation: vec![0f64; num_operators * num_operators],
+            outputs: vec![0f64; num_operators],
+        }
+    }
+
+    /// All the operators in one chain: the last operator modulates the one before it, and so on,
+    /// down to operator 0, which is the only carrier.
+    pub fn stack(num_operators: usize) -> Algorithm {
+        let mut algorithm = Algorithm::new(num_operators);
+        for i in 1..num_operators {
+            algorithm.set_modulation(i, i - 1, 1.0);
+        }
+        if num_operators > 0 {
+            algorithm.set_output(0, 1.0);
+        }
+        algorithm
+    }
+
+    /// Pairs of operators: operator 1 modulates operator 0, 3 modulates 2, etc., and the
+    /// even-numbered operators are all carriers (mixed at equal levels).
+    pub fn pairs(num_operators: usize) -> Algorithm {
+        let mut algorithm = Algorithm::new(num_operators);
+        let carriers = num_operators.div_ceil(2);
+        for i in (0..num_operators).filter(|i| i % 2 == 0) {
+            algorithm.set_output(i, 1.0 / carriers as f64);
+            if i + 1 < num_operators {
+                algorithm.set_modulation(i + 1, i, 1.0);
+            }
+        }
+        algorithm
+    }
+
+    /// No modulation at all: every operator is a carrier (mixed at equal levels), which makes the
+    /// engine an additive synthesizer.
+    pub fn parallel(num_operators: usize) -> Algorithm {
+        let mut algorithm = Algorithm::new(num_operators);
+        for i in 0..num_operators {
+            algorithm.set_output(i, 1.0 / num_operators as f64);
+        }
+        algorithm
+    }
+
+    /// Number of operators this algorithm routes.
+    pub fn num_operators(&self) -> usize {
+        self.num_operators
+    }
+
+    /// Sets how strongly operator `from` modulates operator `to` (its output gets multiplied by
+    /// `amount`, and added to the phase of `to` in radians).
+    ///
+    /// Higher-numbered operators modulating lower-numbered ones is the usual direction: the
+    /// modulation then happens within the same sample. Any other connection is a sample late.
+    pub fn set_modulation(&mut self, from: usize, to: usize, amount: f64) {
+        assert!(from < self.num_operators && to < self.num_operators);
+        self.modulation[from * self.num_operators + to] = amount;
+    }
+
+    /// Sets how strongly an operator modulates itself.
+    pub fn set_feedback(&mut self, operator: usize, amount: f64) {
+        self.set_modulation(operator, operator, amount);
+    }
+
+    /// Sets the level an operator is mixed into the output at (0.0 for a pure modulator).
+    pub fn set_output(&mut self, operator: usize, level: f64) {
+        assert!(operator < self.num_operators);
+        self.outputs[operator] = level;
+    }
+
+    /// How strongly operator `from` modulates operator `to`.
+    pub fn modulation(&self, from: usize, to: usize) -> f64 {
+        self.modulation[from * self.num_operators + to]
+    }
+
+    /// The level an operator is mixed into the output at.
+    pub fn output(&self, operator: usize) -> f64 {
+        self.outputs[operator]
+    }
+}
@@ -0,0 +1,214 @@
+//! FM (frequency modulation) synthesis.
+//!
+//! FM synthesis in the style of the Yamaha DX7: a handful of sine wave "operators", where the
+//! output of some operators (the modulators) wobbles the phase of others (the carriers), and the
+//! carriers are mixed together to make the final sound. Modulating a sine wave's phase with
+//! another sine wave spreads its energy out into sidebands at the carrier frequency plus and minus
+//! multiples of the modulator frequency, so a couple of sine waves can make very rich sounds.
+//!
+//! Strictly speaking this is phase modulation (PM), which is also what the DX7 does: for sine wave
+//! modulators it gives the same sidebands as true frequency modulation, but the pitch doesn't
+//! drift when the modulator has a DC offset (e.g. from feedback).
+//!
+//!  - `Operator`: a sine wave with a frequency ratio (or a fixed frequency), an output level and
+//!    (optionally) an envelope from `dsp::envelopes`
+//!  - `Algorithm`: which operators modulate which, how much feedback there is, and which
+//!    operators are heard
+//!
+//! ```ignore
+//! // Classic two-operator electric piano-ish sound: operator 0 (the carrier) at the played
+//! // frequency, modulated by operator 1 at 14x the frequency, which fades out quickly (higher
+//! // operators modulating lower ones is the usual direction, see `Algorithm::set_modulation`)
+//! let carrier = Operator::new(Frequency::Ratio(1.0), 1.0)
+//!     .with_envelope(Envelope::adsr(0.005, 1.0, 0.6, 0.3));
+//! let modulator = Operator::new(Frequency::Ratio(14.0), 1.5)
+//!     .with_envelope(Envelope::adsr(0.0, 0.3, 0.0, 0.1));
+//! let mut algorithm = Algorithm::new(2);
+//! algorithm.set_modulation(1, 0, 1.0);
+//! algorithm.set_output(0, 1.0);
+//! let mut piano = Fm::new(0.5, 440.0, 0.0, vec![carrier, modulator], algorithm);
+//! piano.note_on();
+//! ```
+
+use std::f64;
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+use dsp::parameter::Parameter;
+
+// FM operators
+pub mod operator;
+pub use self::operator::{Operator, Frequency};
+
+// Operator routing
+pub mod algorithm;
+pub use self::algorithm::Algorithm;
+
+/// FM synthesis engine struct.
+pub struct Fm {
+    amplitude: Parameter,      // Amplitude of the final mix
+    frequency: Parameter,      // Played frequency (in Hz), which operator ratios are relative to
+    offset: Parameter,         // DC offset of the final mix (+/- y axis)
+    operators: Vec<Operator>,
+    algorithm: Algorithm,
+    outputs: Vec<f64>,         // Each operator's output for the current sample
+    previous: Vec<[f64; 2]>,   // Each operator's outputs for the last two samples (for feedback)
+}
+
+impl Fm {
+    /// Creates a new FM synthesis engine.
+    ///
+    /// The algorithm must have one entry per operator. The amplitude, frequency and offset can be
+    /// either an `f64` (a constant value) or another signal, which gets evaluated every sample.
+    pub fn new<A, F, O>(amplitude: A, frequency: F, offset: O, operators: Vec<Operator>,
+                        algorithm: Algorithm) -> Fm
+        where A: Into<Parameter>, F: Into<Parameter>, O: Into<Parameter> {
+        assert!(operators.len() == algorithm.num_operators(),
+                "The algorithm must route exactly as many operators as there are");
+        let num_operators = operators.len();
+
+        Fm {
+            amplitude: amplitude.into(),
+            frequency: frequency.into(),
+            offset: offset.into(),
+            operators,
+            algorithm,
+            outputs: vec![0f64; num_operators],
+            previous: vec![[0f64; 2]; num_operators],
+        }
+    }
+
+    /// Starts a note: every operator's envelope starts its attack.
+    pub fn note_on(&mut self) {
+        for operator in &self.operators {
+            operator.gate_on();
+        }
+    }
+
+    /// Ends a note: every operator's envelope starts its release.
+    pub fn note_off(&mut self) {
+        for operator in &self.operators {
+            operator.gate_off();
+        }
+    }
+
+    /// The operators.
+    pub fn operators_mut(&mut self) -> &mut [Operator] {
+        &mut self.operators
+    }
+
+    /// The operator routing.
+    pub fn algorithm_mut(&mut self) -> &mut Algorithm {
+        &mut self.algorithm
+    }
+}
+
+signal_ops!(Fm);
+
+impl Signal for Fm {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.amplitude.prepare(context);
+        self.frequency.prepare(context);
+        self.offset.prepare(context);
+        for operator in &mut self.operators {
+            operator.prepare(context);
+        }
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let frequency = self.frequency.evaluate();
+        let num_operators = self.operators.len();
+
+        // Operators are evaluated from the last one to the first, so a higher-numbered operator
+        // modulating a lower-numbered one (the usual direction) uses this sample's output. Any
+        // other connection (including feedback) has to use the previous sample's output instead.
+        for i in (0..num_operators).rev() {
+            let mut modulation = 0.0;
+            for j in 0..num_operators {
+                let amount = self.algorithm.modulation(j, i);
+                if amount == 0.0 {
+                    continue;
+                }
+                modulation += amount * if j > i {
+                    self.outputs[j]
+                }
+                else if j == i {
+                    // Averaging the last two samples (like the DX7 does) stops high feedback
+                    // levels from flipping between two values every sample
+                    0.5 * (self.previous[j][0] + self.previous[j][1])
+                }
+                else {
+                    self.previous[j][0]
+                };
+            }
+
+            // The modulation is in radians, so convert it to a percent of a period
+            let phase_modulation = modulation / (2.0 * f64::consts::PI);
+            self.outputs[i] = self.operators[i].evaluate(frequency, phase_modulation);
+        }
+
+        let mut output = 0.0;
+        for i in 0..num_operators {
+            output += self.algorithm.output(i) * self.outputs[i];
+            self.previous[i] = [self.outputs[i], self.previous[i][0]];
+        }
+
+        // Transform the signal, taking into account the amplitude and DC offset
+        output *= self.amplitude.evaluate();
+        output += self.offset.evaluate();
+
+        // Return the output
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64;
+    use dsp::context::ProcessContext;
+    use dsp::envelopes::Envelope;
+    use dsp::generators::fm::{Algorithm, Fm, Frequency, Operator};
+    use dsp::traits::Signal;
+
+    /// Loudest of the next `samples` samples.
+    fn peak(fm: &mut Fm, samples: usize) -> f64 {
+        (0..samples).map(|_| fm.evaluate().abs()).fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn two_operators_match_closed_form() {
+        // Operator 1 modulating operator 0 is sin(2*pi*f*t + index * sin(2*pi*ratio*f*t))
+        let (frequency, ratio, index, sample_rate) = (220.0, 3.5, 2.0, 48000.0);
+        let carrier = Operator::new(Frequency::Ratio(1.0), 1.0);
+        let modulator = Operator::new(Frequency::Ratio(ratio), index);
+        let mut algorithm = Algorithm::new(2);
+        algorithm.set_modulation(1, 0, 1.0);
+        algorithm.set_output(0, 1.0);
+        let mut fm = Fm::new(1.0, frequency, 0.0, vec![carrier, modulator], algorithm);
+        fm.prepare(&ProcessContext::new(sample_rate, 1024));
+
+        for n in 0..4800 {
+            let t = n as f64 / sample_rate;
+            let modulation = index * (2.0 * f64::consts::PI * ratio * frequency * t).sin();
+            let expected = (2.0 * f64::consts::PI * frequency * t + modulation).sin();
+            let output = fm.evaluate();
+            assert!((output - expected).abs() < 1e-9, "sample {}: {} vs {}", n, output, expected);
+        }
+    }
+
+    #[test]
+    fn notes_gate_operator_envelopes() {
+        let carrier = Operator::new(Frequency::Ratio(1.0), 1.0)
+            .with_envelope(Envelope::ar(0.01, 0.01));
+        let mut algorithm = Algorithm::new(1);
+        algorithm.set_output(0, 1.0);
+        let mut fm = Fm::new(1.0, 441.0, 0.0, vec![carrier], algorithm);
+        fm.prepare(&ProcessContext::new(44100.0, 1024));
+
+        assert_eq!(peak(&mut fm, 1000), 0.0);
+        fm.note_on();
+        assert!(peak(&mut fm, 1000) > 0.99);
+        fm.note_off();
+        peak(&mut fm, 441);
+        assert_eq!(peak(&mut fm, 1000), 0.0);
+    }
+}
@@ -0,0 +1,104 @@
+//! FM operator: a phase-modulatable sine wave with an output level and an envelope.
+
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+use dsp::generators::Sine;
+use dsp::envelopes::Envelope;
+
+/// How an operator's frequency is set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Frequency {
+    /// A multiple of the played frequency (whole numbers give harmonic sounds)
+    Ratio(f64),
+    /// A fixed frequency in Hz, whatever frequency is played
+    Fixed(f64),
+}
+
+/// FM operator struct.
+pub struct Operator {
+    frequency: Frequency,  // Ratio or fixed frequency
+    detune: f64,           // Detune (in cents)
+    level: f64,            // Output level; for a modulator, this is the modulation index (in radians)
+    envelope: Option<Envelope>,  // Envelope applied to the output level (None = always fully on)
+    sine: Sine,            // Unit-amplitude sine wave, whose frequency gets set every sample
+}
+
+impl Operator {
+    /// Creates a new operator with the given frequency and output level.
+    ///
+    /// The operator has no envelope to start with, i.e. it's always fully on.
+    pub fn new(frequency: Frequency, level: f64) -> Operator {
+        Operator {
+            frequency,
+            detune: 0.0,
+            level,
+            envelope: None,
+            sine: Sine::new(1.0, 0.0, 0.0),
+        }
+    }
+
+    /// Gives the operator an envelope (any of the usual `envelopes::Envelope` shapes), which
+    /// `Fm::note_on` and `Fm::note_off` open and close the gate of.
+    pub fn with_envelope(mut self, envelope: Envelope) -> Operator {
+        self.envelope = Some(envelope);
+        self
+    }
+
+    /// Detunes the operator by `cents` (hundredths of a semitone).
+    pub fn with_detune(mut self, cents: f64) -> Operator {
+        self.detune = cents;
+        self
+    }
+
+    /// Sets the operator's output level.
+    pub fn set_level(&mut self, level: f64) {
+        self.level = level;
+    }
+
+    /// Sets the operator's frequency.
+    pub fn set_frequency(&mut self, frequency: Frequency) {
+        self.frequency = frequency;
+    }
+
+    /// The operator's envelope (if it has one).
+    pub fn envelope_mut(&mut self) -> Option<&mut Envelope> {
+        self.envelope.as_mut()
+    }
+
+    /// Turns the envelope's gate on (starts a note).
+    pub fn gate_on(&self) {
+        if let Some(ref envelope) = self.envelope {
+            envelope.gate_on();
+        }
+    }
+
+    /// Turns the envelope's gate off (ends a note).
+    pub fn gate_off(&self) {
+        if let Some(ref envelope) = self.envelope {
+            envelope.gate_off();
+        }
+    }
+
+    /// Gets the operator ready to run with the given settings.
+    pub fn prepare(&mut self, context: &ProcessContext) {
+        if let Some(ref mut envelope) = self.envelope {
+            envelope.prepare(context);
+        }
+        self.sine.prepare(context);
+    }
+
+    /// Produces the next sample, for a played frequency of `played_frequency` (in Hz) and a phase
+    /// modulation of `phase_modulation` (as a percent of the whole period).
+    pub fn evaluate(&mut self, played_frequency: f64, phase_modulation: f64) -> f64 {
+        let frequency = match self.frequency {
+            Frequency::Ratio(ratio) => played_frequency * ratio,
+            Frequency::Fixed(frequency) => frequency,
+        };
+        self.sine.set_frequency(frequency * 2f64.powf(self.detune / 1200.0));
+        let envelope = match self.envelope {
+            Some(ref mut envelope) => envelope.evaluate(),
+            None => 1.0,
+        };
+        self.sine.evaluate_phase_modulated(phase_modulation) * self.level * envelope
+    }
+}
@@ -0,0 +1,161 @@
+//! Hard sync: an oscillator that gets restarted by another one.
+//!
+//! Hard sync pairs a "slave" oscillator with a (silent) "master" oscillator. Every time the master
+//! finishes a period, the slave gets reset back to its initial phase, no matter where it was. The
+//! result repeats at the master's frequency, but its timbre comes from however much of the slave's
+//! waveform fits into each master period. Sweeping the slave's frequency while the master stays
+//! put gives the classic "sync sweep" lead sound.
+//!
+//! Each reset makes the slave's output jump, and those jumps alias just like the edges of a naive
+//! saw wave would. They're smoothed out the same way (a PolyBLEP correction on the sample on
+//! either side of the reset), so the synced output is band-limited as long as the slave itself is.
+
+use dsp::traits::{Signal, Oscillator};
+use dsp::context::{ProcessContext, DEFAULT_SAMPLE_RATE};
+use dsp::parameter::Parameter;
+
+/// HardSync struct
+pub struct HardSync {
+    sample_rate: f64,             // Sample rate (for audio playback, etc) - Set by prepare()
+    master_frequency: Parameter,  // Frequency of the master oscillator (in Hz)
+    master_phase: f64,            // Phase of the master oscillator (0.0 to 1.0)
+    slave: Box<Oscillator>,       // Oscillator that gets reset every master period
+    correction: f64,              // PolyBLEP correction left over for the next sample
+}
+
+impl HardSync {
+    /// Creates a new HardSync signal, resetting `slave` every period of a master oscillator
+    /// running at `master_frequency`.
+    pub fn new<F: Into<Parameter>>(master_frequency: F, slave: Box<Oscillator>) -> HardSync {
+        HardSync {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            master_frequency: master_frequency.into(),
+            master_phase: 0.0,
+            slave,
+            correction: 0.0,
+        }
+    }
+
+    /// Restarts both the master and the slave oscillator.
+    pub fn reset(&mut self) {
+        self.master_phase = 0.0;
+        self.correction = 0.0;
+        self.slave.reset();
+    }
+}
+
+signal_ops!(HardSync);
+
+impl Signal for HardSync {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.sample_rate = context.sample_rate;
+        self.master_frequency.prepare(context);
+        self.slave.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let increment = self.master_frequency.evaluate() / self.sample_rate;
+        let next_phase = self.master_phase + increment;
+
+        let mut output = self.correction;
+        self.correction = 0.0;
+
+        if increment > 0.0 && next_phase >= 1.0 {
+            // The master wraps around before the next sample: reset the slave at that point
+            let fraction = (1.0 - self.master_phase) / increment;
+            let (sample, jump) = self.slave.evaluate_synced(fraction);
+
+            // PolyBLEP residual of the jump, for this sample (just before it) and the next sample
+            // (just after it)
+            output += sample + jump * (1.0 - fraction) * (1.0 - fraction) / 2.0;
+            self.correction = -jump * fraction * fraction / 2.0;
+        }
+        else {
+            output += self.slave.evaluate();
+        }
+
+        self.master_phase = next_phase - next_phase.floor();
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dsp::generators::{Antialiasing, Saw, Sine};
+    use dsp::generators::polyblep::tests::{aliasing, FREQUENCY};
+
+    #[test]
+    fn slave_restarts_every_master_period() {
+        // A 1/128 increment is exact in binary, so the master wraps every 128 samples on the dot,
+        // while the 1kHz slave on its own would drift against that
+        let mut sync = HardSync::new(DEFAULT_SAMPLE_RATE / 128.0, Box::new(Saw::new(1.0, 1000.0, 0.0)));
+        let samples: Vec<f64> = (0..1024).map(|_| sync.evaluate()).collect();
+        for n in 128..896 {
+            assert!((samples[n + 128] - samples[n]).abs() < 1e-9, "sample {}", n);
+        }
+    }
+
+    #[test]
+    fn set_phase_and_reset_restore_the_initial_phase() {
+        let mut saw = Saw::new(1.0, 1000.0, 0.0);
+        saw.set_antialiasing(Antialiasing::Naive);
+        saw.set_phase(1.25);
+        assert!((saw.evaluate() + 0.5).abs() < 1e-12);
+        for _ in 0..37 {
+            saw.evaluate();
+        }
+        saw.reset();
+        assert!((saw.evaluate() + 0.5).abs() < 1e-12);
+
+        let mut sine = Sine::new(1.0, 1000.0, 0.0);
+        sine.set_phase(0.25);
+        for _ in 0..37 {
+            sine.evaluate();
+        }
+        sine.reset();
+        assert!((sine.evaluate() - 1.0).abs() < 1e-12);
+
+        sine.reset();
+        let mut sync = HardSync::new(441.0, Box::new(sine));
+        let first = sync.evaluate();
+        for _ in 0..37 {
+            sync.evaluate();
+        }
+        sync.reset();
+        assert!((sync.evaluate() - first).abs() < 1e-12);
+    }
+
+    /// Hard sync that just resets the slave on the sample after the master wraps, with no
+    /// smoothing of the jump.
+    struct NaiveSync {
+        master_phase: f64,
+        slave: Saw,
+    }
+
+    signal_ops!(NaiveSync);
+
+    impl Signal for NaiveSync {
+        fn prepare(&mut self, context: &ProcessContext) {
+            self.slave.prepare(context);
+        }
+
+        fn evaluate(&mut self) -> f64 {
+            let output = self.slave.evaluate();
+            self.master_phase += FREQUENCY as f64 / DEFAULT_SAMPLE_RATE;
+            if self.master_phase >= 1.0 {
+                self.master_phase -= 1.0;
+                self.slave.reset();
+            }
+            output
+        }
+    }
+
+    #[test]
+    fn smoothed_resets_alias_less_than_naive_ones() {
+        let mut naive = NaiveSync { master_phase: 0.0, slave: Saw::new(1.0, 2630.0, 0.0) };
+        let mut sync = HardSync::new(FREQUENCY as f64, Box::new(Saw::new(1.0, 2630.0, 0.0)));
+        let (naive, smoothed) = (aliasing(&mut naive), aliasing(&mut sync));
+        assert!(naive - smoothed > 10.0, "{} dB -> {} dB", naive, smoothed);
+    }
+}
@@ -14,4 +14,38 @@ pub use self::square::Square;
 
 // Triangle wave generator
 pub mod triangle;
-pub use self::triangle::Triangle;
\ No newline at end of file
+pub use self::triangle::Triangle;
+
+// Pulse wave generator
+pub mod pulse;
+pub use self::pulse::Pulse;
+
+// Hard sync (a slave oscillator reset by a master)
+pub mod hard_sync;
+pub use self::hard_sync::HardSync;
+
+// Wavetable generator (arbitrary single-cycle waveforms)
+pub mod wavetable;
+pub use self::wavetable::Wavetable;
+
+// Additive generator (sums of sine wave partials)
+pub mod additive;
+pub use self::additive::{Additive, Partial};
+
+// FM synthesis engine (DX-style operators and algorithms)
+pub mod fm;
+pub use self::fm::Fm;
+
+// Noise generators (white, pink, brown, blue, violet, velvet)
+pub mod noise;
+pub use self::noise::{White, Pink, Brown, Blue, Violet, Velvet};
+
+// Band-limiting (anti-aliasing) helpers
+pub mod polyblep;
+pub use self::polyblep::Antialiasing;
+
+/// Wraps a phase into [0, 1). Unlike `fract()`, this also wraps negative phases (from negative
+/// frequencies) up into range.
+fn wrap_phase(phase: f64) -> f64 {
+    phase - phase.floor()
+}
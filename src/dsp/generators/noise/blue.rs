@@ -0,0 +1,118 @@
+//! Blue and violet noise signal generators
+//!
+//! These are the "opposites" of pink and brown noise: instead of integrating white noise, they
+//! differentiate it (y[n] = x[n] - x[n-1]). Differentiating multiplies the amplitude at each
+//! frequency by the frequency, adding +6dB/octave to the slope:
+//!  - Violet noise is differentiated white noise (0dB + 6dB = +6dB/octave)
+//!  - Blue noise is differentiated pink noise (-3dB + 6dB = +3dB/octave)
+//!
+//! Both are mostly high-frequency hiss, and are useful for dithering.
+
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+use dsp::parameter::Parameter;
+use dsp::generators::noise::{Pink, White};
+use dsp::generators::noise::pink::ROWS;
+
+/// Blue noise generator struct.
+pub struct Blue {
+    amplitude: Parameter,  // Amplitude of the noise
+    offset: Parameter,     // DC offset of the noise (+/- y axis)
+    pink: Pink,            // Unit-amplitude pink noise to differentiate
+    previous: f64,         // Previous pink noise sample
+}
+
+impl Blue {
+    /// Creates a new blue noise generator.
+    ///
+    /// The output is scaled so it has about the same RMS level as uniform white noise of the same
+    /// amplitude.
+    pub fn new<A, O>(amplitude: A, offset: O, seed: u64) -> Blue
+        where A: Into<Parameter>, O: Into<Parameter> {
+        let mut pink = Pink::new(1.0, 0.0, seed);
+        let previous = pink.evaluate();
+        Blue {
+            amplitude: amplitude.into(),
+            offset: offset.into(),
+            pink,
+            previous,
+        }
+    }
+}
+
+signal_ops!(Blue);
+
+impl Signal for Blue {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.amplitude.prepare(context);
+        self.offset.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let current = self.pink.evaluate();
+
+        // The pink noise is (ROWS + 1) uniform values scaled by 1 / sqrt(ROWS + 1), but only two of
+        // them change from one sample to the next (one row, and the extra white value). The
+        // difference of those two pairs has 4x the variance of a single value, so it's
+        // 2 / sqrt(ROWS + 1) times the pink noise's RMS (about 0.49x):
+        let mut output = (current - self.previous) * ((ROWS + 1) as f64).sqrt() / 2.0;
+        self.previous = current;
+
+        // Transform the signal, taking into account the amplitude and DC offset
+        output *= self.amplitude.evaluate();
+        output += self.offset.evaluate();
+
+        // Return the output
+        output
+    }
+}
+
+/// Violet noise generator struct.
+pub struct Violet {
+    amplitude: Parameter,  // Amplitude of the noise
+    offset: Parameter,     // DC offset of the noise (+/- y axis)
+    white: White,          // Unit-amplitude white noise to differentiate
+    previous: f64,         // Previous white noise sample
+}
+
+impl Violet {
+    /// Creates a new violet noise generator.
+    ///
+    /// The output is scaled so it has about the same RMS level as uniform white noise of the same
+    /// amplitude.
+    pub fn new<A, O>(amplitude: A, offset: O, seed: u64) -> Violet
+        where A: Into<Parameter>, O: Into<Parameter> {
+        let mut white = White::new(1.0, 0.0, seed);
+        let previous = white.evaluate();
+        Violet {
+            amplitude: amplitude.into(),
+            offset: offset.into(),
+            white,
+            previous,
+        }
+    }
+}
+
+signal_ops!(Violet);
+
+impl Signal for Violet {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.amplitude.prepare(context);
+        self.offset.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let current = self.white.evaluate();
+
+        // The difference of two independent values has twice the variance of either one
+        let mut output = (current - self.previous) / 2f64.sqrt();
+        self.previous = current;
+
+        // Transform the signal, taking into account the amplitude and DC offset
+        output *= self.amplitude.evaluate();
+        output += self.offset.evaluate();
+
+        // Return the output
+        output
+    }
+}
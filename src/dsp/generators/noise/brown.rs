@@ -0,0 +1,79 @@
+//! Brown (red) noise signal generator
+//!
+//! Brown noise is named after Brownian motion rather than the colour: it's a random walk, i.e.
+//! the running sum (integral) of white noise. Integrating divides the amplitude at each frequency
+//! by the frequency, so the power spectrum falls off at -6dB/octave.
+//!
+//! A pure random walk drifts off to infinity, so this uses a "leaky" integrator that slowly pulls
+//! the output back towards zero. That flattens out the spectrum below about LEAK_FREQUENCY, which
+//! is well below anything audible.
+
+use dsp::traits::Signal;
+use dsp::context::{ProcessContext, DEFAULT_SAMPLE_RATE};
+use dsp::parameter::Parameter;
+use dsp::generators::noise::Random;
+use std::f64;
+
+// Frequency below which the spectrum stops falling, in Hz:
+const LEAK_FREQUENCY: f64 = 5.0;
+
+/// Brown noise generator struct.
+pub struct Brown {
+    amplitude: Parameter,  // Amplitude of the noise
+    offset: Parameter,     // DC offset of the noise (+/- y axis)
+    random: Random,
+    leak: f64,             // How much of the output leaks away each sample
+    gain: f64,             // Input gain that keeps the output at a sensible level
+    state: f64,            // The integrator
+}
+
+impl Brown {
+    /// Creates a new brown noise generator.
+    ///
+    /// The output is scaled so it has about the same RMS level as uniform white noise of the same
+    /// amplitude.
+    pub fn new<A, O>(amplitude: A, offset: O, seed: u64) -> Brown
+        where A: Into<Parameter>, O: Into<Parameter> {
+        let mut brown = Brown {
+            amplitude: amplitude.into(),
+            offset: offset.into(),
+            random: Random::new(seed),
+            leak: 0.0,
+            gain: 0.0,
+            state: 0.0,
+        };
+        brown.set_sample_rate(DEFAULT_SAMPLE_RATE);
+        brown
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f64) {
+        self.leak = 2.0 * f64::consts::PI * LEAK_FREQUENCY / sample_rate;
+
+        // The output of y = (1 - leak) * y + gain * x has a variance of
+        // gain^2 * var(x) / (1 - (1 - leak)^2), so pick the gain that makes that equal var(x):
+        let pole = 1.0 - self.leak;
+        self.gain = (1.0 - pole * pole).sqrt();
+    }
+}
+
+signal_ops!(Brown);
+
+impl Signal for Brown {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.set_sample_rate(context.sample_rate);
+        self.amplitude.prepare(context);
+        self.offset.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        self.state = (1.0 - self.leak) * self.state + self.gain * self.random.uniform();
+        let mut output = self.state;
+
+        // Transform the signal, taking into account the amplitude and DC offset
+        output *= self.amplitude.evaluate();
+        output += self.offset.evaluate();
+
+        // Return the output
+        output
+    }
+}
@@ -0,0 +1,171 @@
+//! Noise generators.
+//!
+//! Noise "colours" are named after the slope of their power spectrum:
+//!  - White noise has the same power at every frequency (0dB/octave)
+//!  - Pink noise falls off at -3dB/octave (the same power in every octave)
+//!  - Brown (red) noise falls off at -6dB/octave
+//!  - Blue noise rises at +3dB/octave
+//!  - Violet noise rises at +6dB/octave
+//!
+//! Velvet noise is different: it's a sparse train of +1/-1 impulses at random positions, which
+//! sounds smoother than white noise and is very cheap to convolve with (handy for reverbs).
+//!
+//! Every noise generator takes a seed, and always produces exactly the same sequence of samples
+//! for the same seed, so tests and renders are repeatable.
+
+// Seedable random number generator used by all the noise generators
+pub mod random;
+pub use self::random::Random;
+
+// White noise generator (uniform and Gaussian)
+pub mod white;
+pub use self::white::White;
+
+// Pink noise generator
+pub mod pink;
+pub use self::pink::Pink;
+
+// Brown noise generator
+pub mod brown;
+pub use self::brown::Brown;
+
+// Blue and violet noise generators
+pub mod blue;
+pub use self::blue::{Blue, Violet};
+
+// Velvet noise generator
+pub mod velvet;
+pub use self::velvet::Velvet;
+
+#[cfg(test)]
+mod tests {
+    use dsp::dft::vec_to_polar;
+    use dsp::window::Window;
+    use dsp::generators::noise::{White, Pink, Brown, Blue, Violet, Velvet};
+    use dsp::traits::Signal;
+
+    const FRAME_SIZE: usize = 4096;
+    const FRAMES: usize = 64;
+
+    /// Average power spectrum of `FRAMES` consecutive frames of the signal (Hann windowed, since
+    /// leakage from the loud high frequencies would otherwise fill in violet noise's low ones).
+    fn power_spectrum(signal: &mut Signal) -> Vec<f64> {
+        let mut power = vec![0f64; FRAME_SIZE / 2 + 1];
+        let mut frame = vec![0f64; FRAME_SIZE];
+        for _ in 0..FRAMES {
+            signal.evaluate_block(&mut frame);
+            let (magnitude, _) = vec_to_polar(Window::Hann.apply(frame.clone()));
+            for (p, m) in power.iter_mut().zip(magnitude) {
+                *p += m * m / (FRAMES as f64);
+            }
+        }
+        power
+    }
+
+    /// Slope of the power spectrum in dB/octave: a least squares fit to the average power of each
+    /// octave from bin 4 up to bin 512 (an eighth of the sample rate).
+    fn slope(signal: &mut Signal) -> f64 {
+        let power = power_spectrum(signal);
+        let octaves: Vec<(f64, f64)> = (2..9)
+            .map(|octave| {
+                let bins = &power[(1 << octave)..(2 << octave)];
+                let mean = bins.iter().sum::<f64>() / (bins.len() as f64);
+                (octave as f64, 10.0 * mean.log10())
+            })
+            .collect();
+        let n = octaves.len() as f64;
+        let mean_x = octaves.iter().map(|&(x, _)| x).sum::<f64>() / n;
+        let mean_y = octaves.iter().map(|&(_, y)| y).sum::<f64>() / n;
+        let covariance: f64 = octaves.iter().map(|&(x, y)| (x - mean_x) * (y - mean_y)).sum();
+        let variance: f64 = octaves.iter().map(|&(x, _)| (x - mean_x) * (x - mean_x)).sum();
+        covariance / variance
+    }
+
+    fn rms(signal: &mut Signal) -> f64 {
+        let mut samples = vec![0f64; FRAME_SIZE * FRAMES];
+        signal.evaluate_block(&mut samples);
+        (samples.iter().map(|x| x * x).sum::<f64>() / (samples.len() as f64)).sqrt()
+    }
+
+    #[test]
+    fn spectral_slopes() {
+        let cases: Vec<(Box<Signal>, f64)> = vec![
+            (Box::new(White::new(1.0, 0.0, 1)), 0.0),
+            (Box::new(Pink::new(1.0, 0.0, 2)), -3.0),
+            (Box::new(Brown::new(1.0, 0.0, 3)), -6.0),
+            (Box::new(Blue::new(1.0, 0.0, 4)), 3.0),
+            (Box::new(Violet::new(1.0, 0.0, 5)), 6.0),
+        ];
+        for (mut noise, expected) in cases {
+            let slope = slope(&mut *noise);
+            assert!((slope - expected).abs() < 0.5, "{} dB/octave, expected {}", slope, expected);
+        }
+    }
+
+    #[test]
+    fn same_level_as_white_noise() {
+        let white = rms(&mut White::new(1.0, 0.0, 1));
+        let others: Vec<Box<Signal>> = vec![
+            Box::new(Pink::new(1.0, 0.0, 2)),
+            Box::new(Brown::new(1.0, 0.0, 3)),
+            Box::new(Blue::new(1.0, 0.0, 4)),
+            Box::new(Violet::new(1.0, 0.0, 5)),
+        ];
+        for mut noise in others {
+            let level = rms(&mut *noise);
+            assert!((level / white - 1.0).abs() < 0.05, "RMS {}, white noise {}", level, white);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn velvet_needs_positive_density() {
+        Velvet::new(1.0, 0.0, 0.0, 1);
+    }
+
+    #[test]
+    fn velvet_has_one_impulse_per_period() {
+        // 2205 impulses per second at 44.1kHz: one impulse in every 20 samples
+        let mut velvet = Velvet::new(1.0, 0.0, 2205.0, 6);
+        let mut samples = vec![0f64; 44100];
+        velvet.evaluate_block(&mut samples);
+        for period in samples.chunks(20) {
+            let impulses: Vec<&f64> = period.iter().filter(|&&x| x != 0.0).collect();
+            assert_eq!(impulses.len(), 1);
+            assert_eq!(impulses[0].abs(), 1.0);
+        }
+
+        // Periods of 44.1 samples still average out to the right density
+        let mut velvet = Velvet::new(1.0, 0.0, 1000.0, 7);
+        velvet.evaluate_block(&mut samples);
+        let impulses = samples.iter().filter(|&&x| x != 0.0).count();
+        assert!(impulses == 1000 || impulses == 1001, "{} impulses", impulses);
+    }
+
+    #[test]
+    fn equal_seeds_give_equal_noise() {
+        fn generate(mut noise: Box<Signal>) -> Vec<f64> {
+            let mut samples = vec![0f64; 4096];
+            noise.evaluate_block(&mut samples);
+            samples
+        }
+        let white = |seed| generate(Box::new(White::new(1.0, 0.0, seed)));
+        let pink = |seed| generate(Box::new(Pink::new(1.0, 0.0, seed)));
+        let velvet = |seed| generate(Box::new(Velvet::new(1.0, 0.0, 2000.0, seed)));
+        assert_eq!(white(8), white(8));
+        assert_eq!(pink(8), pink(8));
+        assert_eq!(velvet(8), velvet(8));
+        assert!(white(8) != white(9));
+    }
+
+    #[test]
+    fn gaussian_white_noise_has_unit_variance() {
+        let mut samples = vec![0f64; FRAME_SIZE * FRAMES];
+        White::new_gaussian(1.0, 0.0, 10).evaluate_block(&mut samples);
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let variance = samples.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n;
+        assert!(mean.abs() < 0.01, "mean {}", mean);
+        assert!((variance - 1.0).abs() < 0.02, "variance {}", variance);
+    }
+}
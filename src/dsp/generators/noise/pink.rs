@@ -0,0 +1,85 @@
+//! Pink noise signal generator
+//!
+//! Pink noise has equal power in every octave (so its power spectrum falls off at -3dB/octave),
+//! which makes it sound much more "even" to human ears than white noise.
+//!
+//! This uses the Voss-McCartney algorithm: a bank of white noise values, where value i only gets
+//! replaced with a new random number every 2^(i+1) samples. Adding them all up gives a signal whose
+//! low frequencies come from the slowly-changing values and whose high frequencies come from the
+//! quickly-changing ones, which works out to an approximately -3dB/octave slope. An extra white
+//! noise value gets added every sample to fill in the top octave.
+
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+use dsp::parameter::Parameter;
+use dsp::generators::noise::Random;
+
+/// Number of values in the bank; the slope holds for about this many octaves below Nyquist
+pub const ROWS: usize = 16;
+
+/// Pink noise generator struct.
+pub struct Pink {
+    amplitude: Parameter,  // Amplitude of the noise
+    offset: Parameter,     // DC offset of the noise (+/- y axis)
+    random: Random,
+    rows: [f64; ROWS],     // The bank of held white noise values
+    sum: f64,              // Running total of `rows`
+    counter: u32,          // Sample counter, used to pick which row to update
+}
+
+impl Pink {
+    /// Creates a new pink noise generator.
+    ///
+    /// The output is scaled so it has about the same RMS level as uniform white noise of the same
+    /// amplitude.
+    pub fn new<A, O>(amplitude: A, offset: O, seed: u64) -> Pink
+        where A: Into<Parameter>, O: Into<Parameter> {
+        let mut random = Random::new(seed);
+        let mut rows = [0f64; ROWS];
+        for row in rows.iter_mut() {
+            *row = random.uniform();
+        }
+        let sum = rows.iter().sum();
+
+        Pink {
+            amplitude: amplitude.into(),
+            offset: offset.into(),
+            random,
+            rows,
+            sum,
+            counter: 0,
+        }
+    }
+}
+
+signal_ops!(Pink);
+
+impl Signal for Pink {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.amplitude.prepare(context);
+        self.offset.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        // Row i gets updated every 2^(i+1) samples: that's exactly the row numbered by how many
+        // trailing zeros the counter has (and only one row changes per sample).
+        self.counter = self.counter.wrapping_add(1);
+        let row = self.counter.trailing_zeros() as usize;
+        if row < ROWS {
+            let new_value = self.random.uniform();
+            self.sum += new_value - self.rows[row];
+            self.rows[row] = new_value;
+        }
+
+        // (ROWS + 1) independent uniform values, so divide by sqrt(ROWS + 1) to get back to the
+        // RMS level of a single one:
+        let mut output = (self.sum + self.random.uniform()) / ((ROWS + 1) as f64).sqrt();
+
+        // Transform the signal, taking into account the amplitude and DC offset
+        output *= self.amplitude.evaluate();
+        output += self.offset.evaluate();
+
+        // Return the output
+        output
+    }
+}
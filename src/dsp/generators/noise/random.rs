@@ -0,0 +1,64 @@
+//! Seedable pseudo-random number generator.
+//!
+//! This is xorshift64* (a 64-bit xorshift generator with a multiplicative output scrambler). It's
+//! nowhere near good enough for cryptography, but it's fast, has a period of 2^64 - 1, and its
+//! output is plenty random for audio.
+
+use std::f64;
+
+/// Random number generator struct.
+#[derive(Clone, Debug)]
+pub struct Random {
+    state: u64,                 // xorshift state (never zero)
+    spare_gaussian: Option<f64>,  // Box-Muller makes two Gaussian values at a time
+}
+
+impl Random {
+    /// Creates a new random number generator. The same seed always gives the same sequence.
+    pub fn new(seed: u64) -> Random {
+        // Run the seed through a round of splitmix64 so that similar seeds (0, 1, 2, ...) still
+        // give completely different sequences, and so the state can't end up as zero:
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+
+        Random {
+            state: if z == 0 { 0x9E37_79B9_7F4A_7C15 } else { z },
+            spare_gaussian: None,
+        }
+    }
+
+    /// Next random 64-bit integer.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Random number from 0.0 (inclusive) to 1.0 (exclusive), uniformly distributed.
+    pub fn next_f64(&mut self) -> f64 {
+        // The top 53 bits fill an f64's mantissa exactly
+        ((self.next_u64() >> 11) as f64) / ((1u64 << 53) as f64)
+    }
+
+    /// Random number from -1.0 to 1.0, uniformly distributed.
+    pub fn uniform(&mut self) -> f64 {
+        self.next_f64() * 2.0 - 1.0
+    }
+
+    /// Random number from a Gaussian (normal) distribution with a mean of 0.0 and a standard
+    /// deviation of 1.0.
+    pub fn gaussian(&mut self) -> f64 {
+        if let Some(spare) = self.spare_gaussian.take() {
+            return spare;
+        }
+
+        // Box-Muller transform (1 - next_f64() is never 0.0, so the log is always finite):
+        let radius = (-2.0 * (1.0 - self.next_f64()).ln()).sqrt();
+        let angle = 2.0 * f64::consts::PI * self.next_f64();
+        self.spare_gaussian = Some(radius * angle.sin());
+        radius * angle.cos()
+    }
+}
@@ -0,0 +1,88 @@
+//! Velvet noise signal generator
+//!
+//! Velvet noise splits time up into equal-length periods (the density sets how many periods there
+//! are per second), and puts exactly one impulse of +1 or -1, at a random position, into each
+//! period. Every other sample is zero. At densities of around 2000 impulses per second and up, it
+//! sounds like a smoother version of white noise, even though 95%+ of its samples are zero.
+
+use dsp::traits::Signal;
+use dsp::context::{ProcessContext, DEFAULT_SAMPLE_RATE};
+use dsp::parameter::Parameter;
+use dsp::generators::noise::Random;
+
+/// Velvet noise generator struct.
+pub struct Velvet {
+    sample_rate: f64,      // Sample rate (for audio playback, etc) - Set by prepare()
+    amplitude: Parameter,  // Amplitude of the impulses
+    offset: Parameter,     // DC offset of the noise (+/- y axis)
+    density: f64,          // Impulses per second
+    random: Random,
+    position: f64,         // Position within the current period, in samples
+    impulse_at: f64,       // Position of this period's impulse, in samples
+    sign: f64,             // Sign of this period's impulse
+}
+
+impl Velvet {
+    /// Creates a new velvet noise generator with `density` impulses per second (more than 0.0).
+    pub fn new<A, O>(amplitude: A, offset: O, density: f64, seed: u64) -> Velvet
+        where A: Into<Parameter>, O: Into<Parameter> {
+        assert!(density > 0.0, "velvet noise density must be more than 0.0");
+        let mut velvet = Velvet {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            amplitude: amplitude.into(),
+            offset: offset.into(),
+            density,
+            random: Random::new(seed),
+            position: 0.0,
+            impulse_at: 0.0,
+            sign: 1.0,
+        };
+        velvet.start_period();
+        velvet
+    }
+
+    /// Length of each period, in samples.
+    fn period(&self) -> f64 {
+        self.sample_rate / self.density
+    }
+
+    /// Picks where (and which way) the impulse in the period starting at `position` goes.
+    fn start_period(&mut self) {
+        // The impulse has to land on a sample, and the samples in this period are at
+        // position, position + 1, position + 2, ...
+        let samples = (self.period() - self.position).ceil().max(1.0);
+        self.impulse_at = self.position + (self.random.next_f64() * samples).floor();
+        self.sign = if self.random.next_u64() >> 63 == 0 { 1.0 } else { -1.0 };
+    }
+}
+
+signal_ops!(Velvet);
+
+impl Signal for Velvet {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.sample_rate = context.sample_rate;
+        self.amplitude.prepare(context);
+        self.offset.prepare(context);
+        self.position = 0.0;
+        self.start_period();
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let mut output = if (self.position - self.impulse_at).abs() < 0.5 { self.sign } else { 0.0 };
+
+        // Periods don't have to be a whole number of samples long, so carry the leftover fraction
+        // of a sample over into the next period:
+        self.position += 1.0;
+        if self.position >= self.period() {
+            self.position -= self.period();
+            self.start_period();
+        }
+
+        // Transform the signal, taking into account the amplitude and DC offset
+        output *= self.amplitude.evaluate();
+        output += self.offset.evaluate();
+
+        // Return the output
+        output
+    }
+}
@@ -0,0 +1,76 @@
+//! White noise signal generator
+//!
+//! White noise has equal power at every frequency, all the way up to the Nyquist frequency. Each
+//! sample is completely independent of all the others.
+
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+use dsp::parameter::Parameter;
+use dsp::generators::noise::Random;
+
+/// Distribution the samples of white noise are drawn from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distribution {
+    /// Evenly spread between -amplitude and amplitude
+    Uniform,
+    /// Gaussian (normal) distribution with a standard deviation of amplitude (so the occasional
+    /// sample goes well past amplitude)
+    Gaussian,
+}
+
+/// White noise generator struct.
+pub struct White {
+    amplitude: Parameter,  // Amplitude of the noise
+    offset: Parameter,     // DC offset of the noise (+/- y axis)
+    distribution: Distribution,
+    random: Random,
+}
+
+impl White {
+    /// Creates a new uniform white noise generator.
+    pub fn new<A, O>(amplitude: A, offset: O, seed: u64) -> White
+        where A: Into<Parameter>, O: Into<Parameter> {
+        White::new_with_distribution(amplitude, offset, seed, Distribution::Uniform)
+    }
+
+    /// Creates a new Gaussian white noise generator.
+    pub fn new_gaussian<A, O>(amplitude: A, offset: O, seed: u64) -> White
+        where A: Into<Parameter>, O: Into<Parameter> {
+        White::new_with_distribution(amplitude, offset, seed, Distribution::Gaussian)
+    }
+
+    /// Creates a new white noise generator with the given distribution.
+    pub fn new_with_distribution<A, O>(amplitude: A, offset: O, seed: u64,
+                                       distribution: Distribution) -> White
+        where A: Into<Parameter>, O: Into<Parameter> {
+        White {
+            amplitude: amplitude.into(),
+            offset: offset.into(),
+            distribution,
+            random: Random::new(seed),
+        }
+    }
+}
+
+signal_ops!(White);
+
+impl Signal for White {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.amplitude.prepare(context);
+        self.offset.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let mut output = match self.distribution {
+            Distribution::Uniform => self.random.uniform(),
+            Distribution::Gaussian => self.random.gaussian(),
+        };
+
+        // Transform the signal, taking into account the amplitude and DC offset
+        output *= self.amplitude.evaluate();
+        output += self.offset.evaluate();
+
+        // Return the output
+        output
+    }
+}
@@ -0,0 +1,127 @@
+//! Band-limiting helpers for the generators with discontinuities (PolyBLEP / PolyBLAMP).
+//!
+//! A naive saw or square wave jumps instantly from one value to another, and a naive triangle wave
+//! changes slope instantly. Those "corners" contain harmonics all the way up to infinity, and every
+//! harmonic above the Nyquist frequency folds back down as an inharmonic alias.
+//!
+//! A band-limited step (BLEP) is what a jump would look like if it had been low-pass filtered
+//! before sampling. PolyBLEP approximates the difference between a BLEP and the naive jump with a
+//! short polynomial that only affects the samples right next to the jump, so the oscillator can
+//! stay a cheap time-domain one and just patch up its discontinuities. PolyBLAMP does the same for
+//! jumps in slope (it's the integral of PolyBLEP).
+//!
+//! Both functions take the oscillator's phase `t` (0.0 to 1.0, with the discontinuity at 0.0) and
+//! phase increment per sample `dt`.
+
+/// How a generator deals with aliasing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Antialiasing {
+    /// Naive time-domain waveform (aliases, but it's the "textbook" shape sample-for-sample)
+    Naive,
+    /// PolyBLEP/PolyBLAMP corrections around every discontinuity
+    PolyBlep,
+}
+
+/// Correction for a step of -2.0 at phase 0.0 (subtract it from the naive waveform). For a step of
+/// size h, scale it by -h/2.
+pub fn poly_blep(t: f64, dt: f64) -> f64 {
+    if dt <= 0.0 {
+        0.0
+    }
+    else if t < dt {
+        // Just after the discontinuity
+        let t = t / dt;
+        2.0 * t - t * t - 1.0
+    }
+    else if t > 1.0 - dt {
+        // Just before the discontinuity
+        let t = (t - 1.0) / dt;
+        t * t + 2.0 * t + 1.0
+    }
+    else {
+        0.0
+    }
+}
+
+/// Correction for a change in slope of +1.0 per sample at phase 0.0 (add it to the naive
+/// waveform). For a slope change of s per sample, scale it by s.
+pub fn poly_blamp(t: f64, dt: f64) -> f64 {
+    if dt <= 0.0 {
+        0.0
+    }
+    else if t < dt {
+        // Just after the discontinuity
+        let t = t / dt - 1.0;
+        -t * t * t / 6.0
+    }
+    else if t > 1.0 - dt {
+        // Just before the discontinuity
+        let t = (t - 1.0) / dt + 1.0;
+        t * t * t / 6.0
+    }
+    else {
+        0.0
+    }
+}
+
+/// Aliasing measurements shared with the other generators' tests.
+#[cfg(test)]
+pub mod tests {
+    use dsp::context::ProcessContext;
+    use dsp::dft::vec_to_polar;
+    use dsp::generators::{Antialiasing, Saw, Square, Triangle, Pulse};
+    use dsp::traits::Signal;
+
+    // One second at 44.1kHz, so every DFT bin is exactly 1Hz wide, and a 1kHz wave's harmonics
+    // land exactly on every 1000th bin. Its aliases (44100m - 1000k Hz) never do.
+    /// Sample rate `aliasing` runs at.
+    pub const SAMPLE_RATE: usize = 44100;
+    /// Fundamental frequency `aliasing` expects.
+    pub const FREQUENCY: usize = 1000;
+
+    /// Power in the inharmonic bins relative to the power in the harmonic ones, in dB.
+    pub fn aliasing(signal: &mut Signal) -> f64 {
+        signal.prepare(&ProcessContext::new(SAMPLE_RATE as f64, SAMPLE_RATE));
+        let mut samples = vec![0f64; SAMPLE_RATE];
+        signal.evaluate_block(&mut samples);
+        let (magnitude, _) = vec_to_polar(samples);
+
+        let (mut harmonic, mut inharmonic) = (0.0, 0.0);
+        for (bin, m) in magnitude.iter().enumerate().skip(1) {
+            if bin % FREQUENCY == 0 {
+                harmonic += m * m;
+            }
+            else {
+                inharmonic += m * m;
+            }
+        }
+        10.0 * (inharmonic / harmonic).log10()
+    }
+
+    /// Aliasing of a saw, a square, a triangle and a 25% pulse wave.
+    fn aliasing_of_each(antialiasing: Antialiasing) -> Vec<f64> {
+        let mut saw = Saw::new(1.0, FREQUENCY as f64, 0.0);
+        saw.set_antialiasing(antialiasing);
+        let mut square = Square::new(1.0, FREQUENCY as f64, 0.0);
+        square.set_antialiasing(antialiasing);
+        let mut triangle = Triangle::new(1.0, FREQUENCY as f64, 0.0);
+        triangle.set_antialiasing(antialiasing);
+        let mut pulse = Pulse::new(1.0, FREQUENCY as f64, 0.0, 0.25);
+        pulse.set_antialiasing(antialiasing);
+        vec![aliasing(&mut saw), aliasing(&mut square), aliasing(&mut triangle), aliasing(&mut pulse)]
+    }
+
+    #[test]
+    fn poly_blep_lowers_aliasing() {
+        // Least improvement over the naive wave and highest aliasing allowed with PolyBLEP, in dB,
+        // for each wave above. The triangle's harmonics already fall at 12dB/octave, so it starts
+        // out far cleaner and gains less.
+        let limits = [(15.0, -30.0), (15.0, -30.0), (10.0, -58.0), (15.0, -30.0)];
+        let naive = aliasing_of_each(Antialiasing::Naive);
+        let poly_blep = aliasing_of_each(Antialiasing::PolyBlep);
+        for ((naive, poly_blep), &(improvement, ceiling)) in naive.iter().zip(&poly_blep).zip(&limits) {
+            assert!(naive - poly_blep > improvement, "{} dB -> {} dB", naive, poly_blep);
+            assert!(*poly_blep < ceiling, "{} dB", poly_blep);
+        }
+    }
+}
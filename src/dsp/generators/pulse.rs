@@ -0,0 +1,193 @@
+//! Pulse wave signal generator.
+//!
+//! The pulse wave is the generalization of the square wave: it still only switches between -1.0 and
+//! 1.0, but the fraction of each period it spends at 1.0 (the "duty cycle") can be anything between
+//! 0.0 and 1.0. A duty cycle of 0.5 is exactly the `Square` wave (low for the first half of the
+//! period, high for the second half), and a duty cycle of d has a DC level of (2d - 1).
+//!
+//! In the frequency domain, harmonic x of a pulse wave has an amplitude proportional to
+//! sin(pi * x * d) / x. So as the duty cycle moves away from 0.5, the even harmonics fade in, and
+//! every (1/d)th harmonic drops out. Sweeping the duty cycle with a slow LFO ("pulse-width
+//! modulation", or PWM) gives the classic moving, chorus-like analog synth sound.
+//!
+//! Like the saw and square waves, the pulse wave is band-limited with PolyBLEP by default (see
+//! `set_antialiasing`).
+
+use dsp::traits::{Signal, Oscillator};
+use dsp::context::{ProcessContext, DEFAULT_SAMPLE_RATE};
+use dsp::parameter::Parameter;
+use dsp::generators::wrap_phase;
+use dsp::generators::polyblep::{Antialiasing, poly_blep};
+
+/// Pulse wave generator struct.
+pub struct Pulse {
+    sample_rate: f64,       // Sample rate (for audio playback, etc) - Set by prepare()
+    amplitude: Parameter,   // Amplitude of the Pulse wave
+    frequency: Parameter,   // Frequency of the Pulse wave (in Hz)
+    offset: Parameter,      // DC offset of the Pulse wave    (+/- y axis)
+    duty_cycle: Parameter,  // Fraction of the period spent at 1.0 (0.0 to 1.0)
+    phase: f64,             // Phase offset of the Pulse wave (+/- x axis, as a percent of the whole period)
+    initial_phase: f64,     // Phase the generator starts at (and goes back to on reset)
+    antialiasing: Antialiasing,  // Whether (and how) to band-limit the discontinuities
+    synced: bool,                // Phase was just reset by HardSync (which smooths that jump itself)
+}
+
+impl Pulse {
+    /// Creates a new Pulse wave signal generator.
+    ///
+    /// Each parameter can be either an `f64` (a constant value) or another signal, which gets
+    /// evaluated every sample. The duty cycle is clamped to the range 0.0 to 1.0; for example, PWM
+    /// from an LFO sweeping the duty cycle between 0.1 and 0.9 is `Sine::new(0.4, 0.5, 0.5)`.
+    pub fn new<A, F, O, D>(amplitude: A, frequency: F, offset: O, duty_cycle: D) -> Pulse
+        where A: Into<Parameter>, F: Into<Parameter>, O: Into<Parameter>, D: Into<Parameter> {
+        Pulse {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            amplitude: amplitude.into(),
+            frequency: frequency.into(),
+            offset: offset.into(),
+            duty_cycle: duty_cycle.into(),
+            phase: 0.0,
+            initial_phase: 0.0,
+            antialiasing: Antialiasing::PolyBlep,
+            synced: false,
+        }
+    }
+
+    /// Chooses how the generator deals with aliasing (the default is `Antialiasing::PolyBlep`).
+    pub fn set_antialiasing(&mut self, antialiasing: Antialiasing) {
+        self.antialiasing = antialiasing;
+    }
+
+    /// Naive (not band-limited) waveform value at `phase`, for a duty cycle from 0.0 to 1.0.
+    fn naive(phase: f64, duty_cycle: f64) -> f64 {
+        match phase {
+            n if n <= 1.0 - duty_cycle => -1.0,
+            _ => 1.0,
+        }
+    }
+
+    /// Waveform value at the current phase, given the phase increment per sample.
+    fn waveform(&self, increment: f64, duty_cycle: f64) -> f64 {
+        // The corrections only depend on how far the phase moves per sample, not which way (it
+        // runs backwards under through-zero FM)
+        let increment = increment.abs();
+        let duty_cycle = duty_cycle.clamp(0.0, 1.0);
+        let mut output = Pulse::naive(self.phase, duty_cycle);
+        if self.antialiasing == Antialiasing::PolyBlep && !self.synced {
+            // Smooth out the jump down to -1.0 at the start of the period, and the jump back up to
+            // 1.0 at (1 - duty cycle)
+            output -= poly_blep(self.phase, increment);
+            output += poly_blep(wrap_phase(self.phase + duty_cycle), increment);
+        }
+        output
+    }
+}
+
+signal_ops!(Pulse);
+
+impl Signal for Pulse {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.sample_rate = context.sample_rate;
+        self.amplitude.prepare(context);
+        self.frequency.prepare(context);
+        self.offset.prepare(context);
+        self.duty_cycle.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let increment = self.frequency.evaluate() / self.sample_rate;
+        let duty_cycle = self.duty_cycle.evaluate();
+        let mut output = self.waveform(increment, duty_cycle);
+        self.synced = false;
+        self.phase = wrap_phase(self.phase + increment);
+
+        // Transform the signal, taking into account the amplitude and DC offset
+        output *= self.amplitude.evaluate();
+        output += self.offset.evaluate();
+
+        // Return the output
+        output
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        let frequency = match self.frequency.constant() {
+            Some(frequency) => frequency,
+            None => {
+                // The frequency is being modulated, so the increment changes every sample anyway
+                for sample in buffer.iter_mut() {
+                    *sample = self.evaluate();
+                }
+                return;
+            },
+        };
+
+        // Same as evaluate(), but the phase increment only gets computed once per block, and the
+        // (slow) floor() only gets called when the phase actually wraps around
+        let increment = frequency / self.sample_rate;
+        for sample in buffer.iter_mut() {
+            let duty_cycle = self.duty_cycle.evaluate();
+            let output = self.waveform(increment, duty_cycle);
+            self.synced = false;
+            self.phase += increment;
+            if self.phase >= 1.0 || self.phase < 0.0 {
+                self.phase -= self.phase.floor();
+            }
+            *sample = output * self.amplitude.evaluate() + self.offset.evaluate();
+        }
+    }
+}
+
+impl Oscillator for Pulse {
+    fn set_phase(&mut self, phase: f64) {
+        self.initial_phase = phase - phase.floor();
+        self.phase = self.initial_phase;
+    }
+
+    fn reset(&mut self) {
+        self.phase = self.initial_phase;
+    }
+
+    fn evaluate_synced(&mut self, fraction: f64) -> (f64, f64) {
+        let increment = self.frequency.evaluate() / self.sample_rate;
+        let duty_cycle = self.duty_cycle.evaluate();
+        let amplitude = self.amplitude.evaluate();
+        let output = self.waveform(increment, duty_cycle) * amplitude + self.offset.evaluate();
+
+        // Run up to the reset, then jump back to the initial phase for the rest of the period
+        let duty_cycle = duty_cycle.clamp(0.0, 1.0);
+        let before = Pulse::naive(wrap_phase(self.phase + fraction * increment), duty_cycle);
+        let after = Pulse::naive(self.initial_phase, duty_cycle);
+        self.phase = wrap_phase(self.initial_phase + (1.0 - fraction) * increment);
+        self.synced = true;
+
+        (output, (after - before) * amplitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dsp::generators::Sine;
+
+    #[test]
+    fn negative_frequencies_run_backwards() {
+        let mut pulse = Pulse::new(1.0, -437.3, 0.0, 0.3);
+        pulse.set_antialiasing(Antialiasing::Naive);
+        let mut buffer = vec![0f64; 1000];
+        pulse.evaluate_block(&mut buffer);
+        for (n, &sample) in buffer.iter().enumerate() {
+            let phase = -437.3 * n as f64 / DEFAULT_SAMPLE_RATE;
+            assert!((sample - Pulse::naive(phase - phase.floor(), 0.3)).abs() < 1e-9, "sample {} is {}", n, sample);
+        }
+    }
+
+    #[test]
+    fn through_zero_fm_stays_in_range() {
+        // The frequency swings between -600Hz and 600Hz, so the phase keeps reversing
+        let mut pulse = Pulse::new(1.0, Sine::new(600.0, 5.0, 0.0), 0.0, 0.3);
+        for n in 0..44100 {
+            let sample = pulse.evaluate();
+            assert!(sample.abs() < 1.1, "sample {} is {}", n, sample);
+        }
+    }
+}
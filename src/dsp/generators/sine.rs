@@ -10,41 +10,151 @@
 //! In the frequency domain, a sine wave represents a "pure tone". It consists of only one
 //! frequency: the frequency of the sine wave itself.
 
-use dsp::traits::Signal;
+use dsp::traits::{Signal, Oscillator};
+use dsp::context::{ProcessContext, DEFAULT_SAMPLE_RATE};
+use dsp::parameter::Parameter;
+use dsp::generators::wrap_phase;
 use std::f64;
 
 /// Sine wave generator struct.
 pub struct Sine {
-    sample_rate: f64,  // Sample rate (for audio playback, etc) - Should be the same throughout the whole project
-    amplitude: f64,    // Amplitude of the Sine wave
-    frequency: f64,    // Frequency of the Sine wave
-    offset: f64,       // DC offset of the Sine wave    (+/- y axis)
-    phase: f64,        // Phase offset of the Sine wave (+/- x axis, as a percent of the whole period)
+    sample_rate: f64,      // Sample rate (for audio playback, etc) - Set by prepare()
+    amplitude: Parameter,  // Amplitude of the Sine wave
+    frequency: Parameter,  // Frequency of the Sine wave (in Hz)
+    offset: Parameter,     // DC offset of the Sine wave    (+/- y axis)
+    phase: f64,            // Phase offset of the Sine wave (+/- x axis, as a percent of the whole period)
+    initial_phase: f64,    // Phase the generator starts at (and goes back to on reset)
 }
 
 impl Sine {
-    /// Creates a new Sine wave signal generator using user-input f64s.
-    pub fn new(amplitude: f64, frequency: f64, offset: f64) -> Sine {
+    /// Creates a new Sine wave signal generator.
+    ///
+    /// Each parameter can be either an `f64` (a constant value) or another signal, which gets
+    /// evaluated every sample (e.g. an LFO for vibrato or tremolo).
+    pub fn new<A, F, O>(amplitude: A, frequency: F, offset: O) -> Sine
+        where A: Into<Parameter>, F: Into<Parameter>, O: Into<Parameter> {
         Sine {
-            sample_rate: 44100.0,
-            amplitude,
-            frequency,
-            offset,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            amplitude: amplitude.into(),
+            frequency: frequency.into(),
+            offset: offset.into(),
             phase: 0.0,
+            initial_phase: 0.0,
         }
     }
-}
 
-impl Signal for Sine {
-    fn evaluate(&mut self) -> f64 {
-        let mut output = (2.0 * f64::consts::PI * (self.phase)).sin();
-        self.phase = (self.phase + self.frequency / self.sample_rate).fract();
+    /// Sets the frequency (in Hz), replacing whatever constant or signal was setting it before.
+    pub fn set_frequency<F: Into<Parameter>>(&mut self, frequency: F) {
+        self.frequency = frequency.into();
+    }
+
+    /// Produces the next sample with the phase pushed ahead by `phase_modulation` (as a percent of
+    /// the whole period) for this sample only. This is how FM operators modulate each other (see
+    /// `generators::fm`).
+    pub fn evaluate_phase_modulated(&mut self, phase_modulation: f64) -> f64 {
+        let mut output = Sine::naive(self.phase + phase_modulation);
+        self.phase = wrap_phase(self.phase + self.frequency.evaluate() / self.sample_rate);
 
         // Transform the signal, taking into account the amplitude and DC offset
-        output *= self.amplitude;
-        output += self.offset;
+        output *= self.amplitude.evaluate();
+        output += self.offset.evaluate();
 
         // Return the output
         output
     }
-}
\ No newline at end of file
+
+    /// Waveform value at `phase`.
+    fn naive(phase: f64) -> f64 {
+        (2.0 * f64::consts::PI * phase).sin()
+    }
+}
+
+signal_ops!(Sine);
+
+impl Signal for Sine {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.sample_rate = context.sample_rate;
+        self.amplitude.prepare(context);
+        self.frequency.prepare(context);
+        self.offset.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        self.evaluate_phase_modulated(0.0)
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        let frequency = match self.frequency.constant() {
+            Some(frequency) => frequency,
+            None => {
+                // The frequency is being modulated, so the increment changes every sample anyway
+                for sample in buffer.iter_mut() {
+                    *sample = self.evaluate();
+                }
+                return;
+            },
+        };
+
+        // Same as evaluate(), but the phase increment only gets computed once per block, and the
+        // (slow) floor() only gets called when the phase actually wraps around
+        let increment = frequency / self.sample_rate;
+        for sample in buffer.iter_mut() {
+            let output = Sine::naive(self.phase);
+            self.phase += increment;
+            if self.phase >= 1.0 || self.phase < 0.0 {
+                self.phase -= self.phase.floor();
+            }
+            *sample = output * self.amplitude.evaluate() + self.offset.evaluate();
+        }
+    }
+}
+
+impl Oscillator for Sine {
+    fn set_phase(&mut self, phase: f64) {
+        self.initial_phase = phase - phase.floor();
+        self.phase = self.initial_phase;
+    }
+
+    fn reset(&mut self) {
+        self.phase = self.initial_phase;
+    }
+
+    fn evaluate_synced(&mut self, fraction: f64) -> (f64, f64) {
+        let increment = self.frequency.evaluate() / self.sample_rate;
+        let amplitude = self.amplitude.evaluate();
+        let output = Sine::naive(self.phase) * amplitude + self.offset.evaluate();
+
+        // Run up to the reset, then jump back to the initial phase for the rest of the period
+        let before = Sine::naive(wrap_phase(self.phase + fraction * increment));
+        let after = Sine::naive(self.initial_phase);
+        self.phase = wrap_phase(self.initial_phase + (1.0 - fraction) * increment);
+
+        (output, (after - before) * amplitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_frequencies_run_backwards() {
+        let mut sine = Sine::new(1.0, -437.3, 0.0);
+        let mut buffer = vec![0f64; 1000];
+        sine.evaluate_block(&mut buffer);
+        for (n, &sample) in buffer.iter().enumerate() {
+            let phase = -437.3 * n as f64 / DEFAULT_SAMPLE_RATE;
+            assert!((sample - Sine::naive(phase - phase.floor())).abs() < 1e-9, "sample {} is {}", n, sample);
+        }
+    }
+
+    #[test]
+    fn through_zero_fm_stays_in_range() {
+        // The frequency swings between -600Hz and 600Hz, so the phase keeps reversing
+        let mut sine = Sine::new(1.0, Sine::new(600.0, 5.0, 0.0), 0.0);
+        for n in 0..44100 {
+            let sample = sine.evaluate();
+            assert!(sample.abs() < 1.1, "sample {} is {}", n, sample);
+        }
+    }
+}
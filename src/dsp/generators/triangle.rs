@@ -1,42 +1,176 @@
 //! Triangle wave generator
+//!
+//! The triangle wave only has odd harmonics, falling off at -12dB/octave. It has no jumps, only
+//! sudden changes in slope, so it aliases much less than the saw or square waves. Like theirs,
+//! those corners get rounded off by default (here with PolyBLAMP corrections) to lower the
+//! aliasing even further; setting the antialiasing mode to `Antialiasing::Naive` turns that off.
 
-use dsp::traits::Signal;
+use dsp::traits::{Signal, Oscillator};
+use dsp::context::{ProcessContext, DEFAULT_SAMPLE_RATE};
+use dsp::parameter::Parameter;
+use dsp::generators::wrap_phase;
+use dsp::generators::polyblep::{Antialiasing, poly_blamp};
 
 /// Triangle wave generator struct.
 pub struct Triangle {
-    sample_rate: f64,  // Sample rate (for audio playback, etc) - Should be the same throughout the whole project
-    amplitude: f64,    // Amplitude of the Square wave
-    frequency: f64,    // Frequency of the Square wave
-    offset: f64,       // DC offset of the Square wave    (+/- y axis)
-    phase: f64,        // Phase offset of the Square wave (+/- x axis, as a percent of the whole period)
+    sample_rate: f64,      // Sample rate (for audio playback, etc) - Set by prepare()
+    amplitude: Parameter,  // Amplitude of the Square wave
+    frequency: Parameter,  // Frequency of the Square wave (in Hz)
+    offset: Parameter,     // DC offset of the Square wave    (+/- y axis)
+    phase: f64,            // Phase offset of the Square wave (+/- x axis, as a percent of the whole period)
+    initial_phase: f64,    // Phase the generator starts at (and goes back to on reset)
+    antialiasing: Antialiasing,  // Whether (and how) to band-limit the discontinuities
+    synced: bool,                // Phase was just reset by HardSync (which smooths that jump itself)
 }
 
 impl Triangle {
-    /// Creates a new Saw wave signal generator.
-    pub fn new(amplitude: f64, frequency: f64, offset: f64) -> Triangle {
+    /// Creates a new Triangle wave signal generator.
+    ///
+    /// Each parameter can be either an `f64` (a constant value) or another signal, which gets
+    /// evaluated every sample (e.g. an LFO for vibrato or tremolo).
+    pub fn new<A, F, O>(amplitude: A, frequency: F, offset: O) -> Triangle
+        where A: Into<Parameter>, F: Into<Parameter>, O: Into<Parameter> {
         Triangle {
-            sample_rate: 44100.0,
-            amplitude,
-            frequency,
-            offset,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            amplitude: amplitude.into(),
+            frequency: frequency.into(),
+            offset: offset.into(),
             phase: 0.0,
+            initial_phase: 0.0,
+            antialiasing: Antialiasing::PolyBlep,
+            synced: false,
         }
     }
+
+    /// Chooses how the generator deals with aliasing (the default is `Antialiasing::PolyBlep`).
+    pub fn set_antialiasing(&mut self, antialiasing: Antialiasing) {
+        self.antialiasing = antialiasing;
+    }
+
+    /// Naive (not band-limited) waveform value at `phase`.
+    fn naive(phase: f64) -> f64 {
+        match phase {
+            n if n <= 0.5 => (phase*2.0)*2.0 - 1.0,
+            _ => ((1.0 - phase)*2.0)*2.0 - 1.0,
+        }
+    }
+
+    /// Waveform value at the current phase, given the phase increment per sample.
+    fn waveform(&self, increment: f64) -> f64 {
+        // The corrections only depend on how far the phase moves per sample, not which way (it
+        // runs backwards under through-zero FM)
+        let increment = increment.abs();
+        let mut output = Triangle::naive(self.phase);
+        if self.antialiasing == Antialiasing::PolyBlep && !self.synced {
+            // Round off the corners: the slope goes from -4 to +4 per period at the start of the
+            // period (a change of +8 * increment per sample), and back again halfway through
+            output += 8.0 * increment * poly_blamp(self.phase, increment);
+            output -= 8.0 * increment * poly_blamp(wrap_phase(self.phase + 0.5), increment);
+        }
+        output
+    }
 }
 
+signal_ops!(Triangle);
+
 impl Signal for Triangle {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.sample_rate = context.sample_rate;
+        self.amplitude.prepare(context);
+        self.frequency.prepare(context);
+        self.offset.prepare(context);
+    }
+
     fn evaluate(&mut self) -> (f64) {
-        let mut output = match self.phase {
-            n if n <= 0.5 => (self.phase*2.0)*2.0 - 1.0,
-            _ => ((1.0 - self.phase)*2.0)*2.0 - 1.0,
-        };
-        self.phase = (self.phase + self.frequency / self.sample_rate).fract();
+        let increment = self.frequency.evaluate() / self.sample_rate;
+        let mut output = self.waveform(increment);
+        self.synced = false;
+        self.phase = wrap_phase(self.phase + increment);
 
         // Transform the signal, taking into account the amplitude and DC offset
-        output *= self.amplitude;
-        output += self.offset;
+        output *= self.amplitude.evaluate();
+        output += self.offset.evaluate();
 
         // Return the output
         output
     }
-}
\ No newline at end of file
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        let frequency = match self.frequency.constant() {
+            Some(frequency) => frequency,
+            None => {
+                // The frequency is being modulated, so the increment changes every sample anyway
+                for sample in buffer.iter_mut() {
+                    *sample = self.evaluate();
+                }
+                return;
+            },
+        };
+
+        // Same as evaluate(), but the phase increment only gets computed once per block, and the
+        // (slow) floor() only gets called when the phase actually wraps around
+        let increment = frequency / self.sample_rate;
+        for sample in buffer.iter_mut() {
+            let output = self.waveform(increment);
+            self.synced = false;
+            self.phase += increment;
+            if self.phase >= 1.0 || self.phase < 0.0 {
+                self.phase -= self.phase.floor();
+            }
+            *sample = output * self.amplitude.evaluate() + self.offset.evaluate();
+        }
+    }
+}
+
+impl Oscillator for Triangle {
+    fn set_phase(&mut self, phase: f64) {
+        self.initial_phase = phase - phase.floor();
+        self.phase = self.initial_phase;
+    }
+
+    fn reset(&mut self) {
+        self.phase = self.initial_phase;
+    }
+
+    fn evaluate_synced(&mut self, fraction: f64) -> (f64, f64) {
+        let increment = self.frequency.evaluate() / self.sample_rate;
+        let amplitude = self.amplitude.evaluate();
+        let output = self.waveform(increment) * amplitude + self.offset.evaluate();
+
+        // Run up to the reset, then jump back to the initial phase for the rest of the period
+        let before = Triangle::naive(wrap_phase(self.phase + fraction * increment));
+        let after = Triangle::naive(self.initial_phase);
+        self.phase = wrap_phase(self.initial_phase + (1.0 - fraction) * increment);
+        self.synced = true;
+
+        (output, (after - before) * amplitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dsp::generators::Sine;
+
+    #[test]
+    fn negative_frequencies_run_backwards() {
+        let mut triangle = Triangle::new(1.0, -437.3, 0.0);
+        triangle.set_antialiasing(Antialiasing::Naive);
+        let mut buffer = vec![0f64; 1000];
+        triangle.evaluate_block(&mut buffer);
+        for (n, &sample) in buffer.iter().enumerate() {
+            let phase = -437.3 * n as f64 / DEFAULT_SAMPLE_RATE;
+            assert!((sample - Triangle::naive(phase - phase.floor())).abs() < 1e-9, "sample {} is {}", n, sample);
+        }
+    }
+
+    #[test]
+    fn through_zero_fm_stays_in_range() {
+        // The frequency swings between -600Hz and 600Hz, so the phase keeps reversing
+        let mut triangle = Triangle::new(1.0, Sine::new(600.0, 5.0, 0.0), 0.0);
+        for n in 0..44100 {
+            let sample = triangle.evaluate();
+            assert!(sample.abs() < 1.1, "sample {} is {}", n, sample);
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! Wavetable signal generator.
+//!
+//! A wavetable oscillator plays back a stored single cycle of a waveform over and over, so it can
+//! produce any timbre at all (not just the "basic waveforms"). Several cycles can be loaded at
+//! once, and the table position (0.0 = first cycle, 1.0 = last cycle) crossfades between them;
+//! modulating the position with another signal makes the timbre move over time.
+//!
+//! Playing a stored cycle back faster raises every harmonic in it, and any harmonic that ends up
+//! above the Nyquist frequency aliases. To avoid that, each cycle gets turned into a "mipmap": a
+//! set of copies with fewer and fewer harmonics (the top octave of harmonics is removed for each
+//! copy, using the FFT). The oscillator then plays the copy with the most harmonics that all stay
+//! below the Nyquist frequency at the current playback frequency.
+//!
+//! Since the tables are stored at a fixed resolution, readout needs to interpolate between the
+//! stored samples; see `Interpolation`.
+
+use std::f64;
+use std::path::Path;
+use hound;
+use dsp::traits::{Signal, Oscillator};
+use dsp::context::{ProcessContext, DEFAULT_SAMPLE_RATE};
+use dsp::parameter::Parameter;
+use dsp::dft::FftPlan;
+use dsp::generators::wrap_phase;
+use dsp::wav::WavFile;
+
+// Length of every stored table (a power of two, so indices can wrap around with a bit mask):
+const TABLE_SIZE: usize = 2048;
+
+// Number of mipmap levels: level 0 has TABLE_SIZE/2 harmonics, and each level halves that, down
+// to a single harmonic (a sine wave)
+const MIPMAP_LEVELS: usize = 11;
+
+// Number of table samples on each side of the read position used by sinc interpolation:
+const SINC_HALF_WIDTH: isize = 4;
+
+/// How the generator reads between the stored samples of a table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolation {
+    /// Straight line between the two nearest samples (cheapest, but dulls the highest harmonics)
+    Linear,
+    /// Cubic (Catmull-Rom) curve through the four nearest samples
+    Cubic,
+    /// Windowed sinc over the eight nearest samples (keeps the highest harmonics closest to their
+    /// full level, but is the most expensive)
+    Sinc,
+}
+
+/// Wavetable generator struct.
+pub struct Wavetable {
+    sample_rate: f64,        // Sample rate (for audio playback, etc) - Set by prepare()
+    amplitude: Parameter,    // Amplitude of the Wavetable
+    frequency: Parameter,    // Frequency of the Wavetable (in Hz)
+    offset: Parameter,       // DC offset of the Wavetable    (+/- y axis)
+    position: Parameter,     // Table position (0.0 = first table, 1.0 = last table)
+    phase: f64,              // Phase offset of the Wavetable (+/- x axis, as a percent of the whole period)
+    initial_phase: f64,      // Phase the generator starts at (and goes back to on reset)
+    interpolation: Interpolation,  // How to read between the stored samples
+    tables: Vec<Vec<Vec<f64>>>,    // Mipmap levels for each table (tables[table][level][sample])
+}
+
+impl Wavetable {
+    /// Creates a new Wavetable signal generator from one or more single-cycle tables.
+    ///
+    /// The tables can be any length (they get resampled to a common length), and all of them
+    /// should start and end at the same point in the cycle. Each parameter can be either an `f64`
+    /// (a constant value) or another signal, which gets evaluated every sample.
+    pub fn new<A, F, O, P>(amplitude: A, frequency: F, offset: O, position: P,
+                           tables: Vec<Vec<f64>>) -> Wavetable
+        where A: Into<Parameter>, F: Into<Parameter>, O: Into<Parameter>, P: Into<Parameter> {
+        assert!(!tables.is_empty(), "A wavetable needs at least one table");
+
+        Wavetable {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            amplitude: amplitude.into(),
+            frequency: frequency.into(),
+            offset: offset.into(),
+            position: position.into(),
+            phase: 0.0,
+            initial_phase: 0.0,
+            interpolation: Interpolation::Cubic,
+            tables: tables.iter().map(|table| mipmap(table)).collect(),
+        }
+    }
+
+    /// Creates a new Wavetable signal generator from a WAV file.
+    ///
+    /// The file is split up into consecutive tables of `cycle_length` samples each (2048 is the
+    /// usual length for wavetable files); a file with only one cycle in it becomes a single
+    /// table. Multi-channel files get mixed down to mono first.
+    pub fn from_wav<A, F, O, P, Q>(amplitude: A, frequency: F, offset: O, position: P,
+                                   path: Q, cycle_length: usize) -> Result<Wavetable, hound::Error>
+        where A: Into<Parameter>, F: Into<Parameter>, O: Into<Parameter>, P: Into<Parameter>,
+              Q: AsRef<Path> {
+        assert!(cycle_length > 0);
+        let samples = try!(WavFile::open(path)).mono();
+        if samples.len() < cycle_length {
+            return Err(hound::Error::FormatError("file is shorter than one cycle"));
+        }
+
+        let tables = samples.chunks(cycle_length)
+            .filter(|cycle| cycle.len() == cycle_length)
+            .map(|cycle| cycle.to_vec())
+            .collect();
+        Ok(Wavetable::new(amplitude, frequency, offset, position, tables))
+    }
+
+    /// Chooses how the generator reads between stored samples (the default is
+    /// `Interpolation::Cubic`).
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) {
+        self.interpolation = interpolation;
+    }
+
+    /// Number of tables loaded.
+    pub fn num_tables(&self) -> usize {
+        self.tables.len()
+    }
+
+    /// Band-limited waveform value at `phase`, given the phase increment per sample and the table
+    /// position.
+    fn waveform(&self, phase: f64, increment: f64, position: f64) -> f64 {
+        // Pick the mipmap level whose highest harmonic (TABLE_SIZE/2 >> level) stays below the
+        // Nyquist frequency, i.e. (TABLE_SIZE/2 >> level) * increment <= 0.5
+        let level = (TABLE_SIZE as f64 * increment.abs()).log2().ceil();
+        let level = if level > 0.0 { (level as usize).min(MIPMAP_LEVELS - 1) } else { 0 };
+
+        // Crossfade between the two tables on either side of the position
+        let position = position.clamp(0.0, 1.0) * (self.tables.len() - 1) as f64;
+        let first = (position.floor() as usize).min(self.tables.len() - 1);
+        let second = (first + 1).min(self.tables.len() - 1);
+        let mix = position - first as f64;
+
+        let index = phase * TABLE_SIZE as f64;
+        let mut output = self.read(&self.tables[first][level], index);
+        if mix > 0.0 {
+            output += (self.read(&self.tables[second][level], index) - output) * mix;
+        }
+        output
+    }
+
+    /// Reads `table` at a fractional `index`.
+    fn read(&self, table: &[f64], index: f64) -> f64 {
+        let whole = index.floor();
+        let fraction = index - whole;
+        let whole = whole as isize;
+        let sample = |offset: isize| table[((whole + offset) as usize) & (TABLE_SIZE - 1)];
+
+        match self.interpolation {
+            Interpolation::Linear => {
+                let (x0, x1) = (sample(0), sample(1));
+                x0 + (x1 - x0) * fraction
+            },
+            Interpolation::Cubic => {
+                let (xm1, x0, x1, x2) = (sample(-1), sample(0), sample(1), sample(2));
+                let c1 = 0.5 * (x1 - xm1);
+                let c2 = xm1 - 2.5 * x0 + 2.0 * x1 - 0.5 * x2;
+                let c3 = 0.5 * (x2 - xm1) + 1.5 * (x0 - x1);
+                ((c3 * fraction + c2) * fraction + c1) * fraction + x0
+            },
+            Interpolation::Sinc => {
+                if fraction == 0.0 {
+                    return sample(0);
+                }
+
+                // sin(pi * (fraction - j)) only flips sign from one j to the next, so it only
+                // needs computing once
+                let sin = (f64::consts::PI * fraction).sin() / f64::consts::PI;
+                let mut output = 0.0;
+                let mut total_weight = 0.0;
+                for j in (1 - SINC_HALF_WIDTH)..(SINC_HALF_WIDTH + 1) {
+                    let t = fraction - j as f64;
+                    let sign = if j % 2 == 0 { 1.0 } else { -1.0 };
+                    let window = 0.5 + 0.5 * (f64::consts::PI * t / SINC_HALF_WIDTH as f64).cos();
+                    let weight = sign * sin / t * window;
+                    output += sample(j) * weight;
+                    total_weight += weight;
+                }
+
+                // The truncated kernel's weights don't quite add up to 1.0 (and how far off they
+                // are depends on the fraction), so normalize them to keep the gain steady
+                output / total_weight
+            },
+        }
+    }
+}
+
+/// Resamples a single cycle to TABLE_SIZE samples, and makes a band-limited copy of it for every
+/// mipmap level.
+fn mipmap(cycle: &[f64]) -> Vec<Vec<f64>> {
+    assert!(!cycle.is_empty(), "Wavetable tables can't be empty");
+
+    // Spectrum of the cycle, scaled so the same harmonics come out at the same amplitude in a
+    // TABLE_SIZE-long table. The input's Nyquist bin (if it has one) is ambiguous, so it's left
+    // out.
+    let (real, imaginary) = FftPlan::new(cycle.len()).process_real(cycle);
+    let scale = TABLE_SIZE as f64 / cycle.len() as f64;
+    let harmonics = cycle.len().div_ceil(2).min(TABLE_SIZE / 2);
+
+    let plan = FftPlan::new(TABLE_SIZE);
+    (0..MIPMAP_LEVELS).map(|level| {
+        let mut level_real = vec![0f64; TABLE_SIZE / 2 + 1];
+        let mut level_imaginary = vec![0f64; TABLE_SIZE / 2 + 1];
+        let top = ((TABLE_SIZE / 2) >> level).min(TABLE_SIZE / 2 - 1).min(harmonics - 1);
+        for k in 0..(top + 1) {
+            level_real[k] = real[k] * scale;
+            level_imaginary[k] = imaginary[k] * scale;
+        }
+        plan.process_real_inverse(&level_real, &level_imaginary)
+    }).collect()
+}
+
+signal_ops!(Wavetable);
+
+impl Signal for Wavetable {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.sample_rate = context.sample_rate;
+        self.amplitude.prepare(context);
+        self.frequency.prepare(context);
+        self.offset.prepare(context);
+        self.position.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let increment = self.frequency.evaluate() / self.sample_rate;
+        let position = self.position.evaluate();
+        let mut output = self.waveform(self.phase, increment, position);
+        self.phase = wrap_phase(self.phase + increment);
+
+        // Transform the signal, taking into account the amplitude and DC offset
+        output *= self.amplitude.evaluate();
+        output += self.offset.evaluate();
+
+        // Return the output
+        output
+    }
+}
+
+impl Oscillator for Wavetable {
+    fn set_phase(&mut self, phase: f64) {
+        self.initial_phase = phase - phase.floor();
+        self.phase = self.initial_phase;
+    }
+
+    fn reset(&mut self) {
+        self.phase = self.initial_phase;
+    }
+
+    fn evaluate_synced(&mut self, fraction: f64) -> (f64, f64) {
+        let increment = self.frequency.evaluate() / self.sample_rate;
+        let position = self.position.evaluate();
+        let amplitude = self.amplitude.evaluate();
+        let output = self.waveform(self.phase, increment, position) * amplitude
+            + self.offset.evaluate();
+
+        // Run up to the reset, then jump back to the initial phase for the rest of the period
+        let before_phase = wrap_phase(self.phase + fraction * increment);
+        let before = self.waveform(before_phase, increment, position);
+        let after = self.waveform(self.initial_phase, increment, position);
+        self.phase = wrap_phase(self.initial_phase + (1.0 - fraction) * increment);
+
+        (output, (after - before) * amplitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dsp::dft::vec_to_polar;
+    use dsp::generators::polyblep::tests::{aliasing, FREQUENCY, SAMPLE_RATE};
+    use dsp::wav::tests::write_temp;
+
+    /// One cycle of a sine wave, `len` samples long.
+    fn sine(len: usize, harmonic: usize) -> Vec<f64> {
+        (0..len)
+            .map(|i| (2.0 * f64::consts::PI * (harmonic * i) as f64 / len as f64).sin())
+            .collect()
+    }
+
+    /// One cycle of a naive saw wave (every harmonic up to the table's Nyquist frequency).
+    fn saw() -> Vec<f64> {
+        (0..TABLE_SIZE).map(|i| 2.0 * i as f64 / TABLE_SIZE as f64 - 1.0).collect()
+    }
+
+    #[test]
+    fn mipmaps_keep_harmonics_below_nyquist() {
+        // At 1kHz the 22nd harmonic is the last one below the Nyquist frequency, so the level with
+        // 16 harmonics gets played
+        let mut wavetable = Wavetable::new(1.0, FREQUENCY as f64, 0.0, 0.0, vec![saw()]);
+        assert!(aliasing(&mut wavetable) < -80.0);
+
+        let mut wavetable = Wavetable::new(1.0, FREQUENCY as f64, 0.0, 0.0, vec![saw()]);
+        let mut samples = vec![0f64; SAMPLE_RATE];
+        wavetable.evaluate_block(&mut samples);
+        let (magnitude, _) = vec_to_polar(samples);
+        let harmonic = |k: usize| magnitude[k * FREQUENCY] / magnitude[FREQUENCY];
+        assert!((harmonic(16) - 1.0 / 16.0).abs() < 1e-3, "16th harmonic at {}", harmonic(16));
+        assert!(harmonic(17) < 1e-6, "17th harmonic at {}", harmonic(17));
+    }
+
+    #[test]
+    fn interpolation_reproduces_a_sine_table() {
+        let cases = [(Interpolation::Linear, 2e-6), (Interpolation::Cubic, 1e-7),
+                     (Interpolation::Sinc, 1e-6)];
+        for &(interpolation, tolerance) in &cases {
+            let mut wavetable = Wavetable::new(1.0, 437.3, 0.0, 0.0, vec![sine(TABLE_SIZE, 1)]);
+            wavetable.set_interpolation(interpolation);
+            for n in 0..1000 {
+                let phase = 2.0 * f64::consts::PI * 437.3 * n as f64 / DEFAULT_SAMPLE_RATE;
+                let expected = phase.sin();
+                let error = (wavetable.evaluate() - expected).abs();
+                assert!(error < tolerance, "{:?}: sample {} off by {}", interpolation, n, error);
+            }
+        }
+    }
+
+    #[test]
+    fn position_crossfades_between_tables() {
+        // Three tables, so position 0.75 is halfway between the second and third
+        let tables = vec![sine(256, 1), sine(256, 2), sine(256, 3)];
+        for &(position, weights) in &[(0.0, [1.0, 0.0, 0.0]), (0.25, [0.5, 0.5, 0.0]),
+                                      (0.75, [0.0, 0.5, 0.5]), (1.0, [0.0, 0.0, 1.0])] {
+            let mut wavetable = Wavetable::new(1.0, 100.0, 0.0, position, tables.clone());
+            for n in 0..1000 {
+                let phase = 2.0 * f64::consts::PI * 100.0 * n as f64 / DEFAULT_SAMPLE_RATE;
+                let expected: f64 = weights.iter().enumerate()
+                    .map(|(k, weight)| weight * (phase * (k + 1) as f64).sin())
+                    .sum();
+                let error = (wavetable.evaluate() - expected).abs();
+                assert!(error < 1e-7, "position {}: sample {} off by {}", position, n, error);
+            }
+        }
+    }
+
+    #[test]
+    fn loads_tables_from_wav() {
+        let cycles = [sine(256, 1), sine(256, 2)].concat();
+        let path = write_temp("wavetable_loads_tables_from_wav.wav", 44100, &[cycles]);
+
+        let mut wavetable = Wavetable::from_wav(1.0, 100.0, 0.0, 1.0, &path, 256).unwrap();
+        assert_eq!(wavetable.num_tables(), 2);
+        for n in 0..1000 {
+            let expected = (4.0 * f64::consts::PI * 100.0 * n as f64 / DEFAULT_SAMPLE_RATE).sin();
+            assert!((wavetable.evaluate() - expected).abs() < 1e-6);
+        }
+
+        assert!(Wavetable::from_wav(1.0, 100.0, 0.0, 0.0, &path, 1024).is_err());
+    }
+}
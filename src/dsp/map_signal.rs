@@ -0,0 +1,39 @@
+//! Signal that passes every sample of another signal through a function
+//!
+//! Handy for one-off waveshaping without writing a whole new signal type, e.g.
+//! `MapSignal::new(Box::new(sine), |x| x * x * x)`, or `|x| x.tanh()` for soft saturation.
+
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+
+/// MapSignal struct
+pub struct MapSignal<F: FnMut(f64) -> f64> {
+    mapped: Box<Signal>,
+    function: F,
+}
+
+impl<F: FnMut(f64) -> f64> MapSignal<F> {
+    /// Creates a new MapSignal signal
+    pub fn new(mapped: Box<Signal>, function: F) -> MapSignal<F> {
+        MapSignal {mapped, function}
+    }
+}
+
+signal_ops!([F: FnMut(f64) -> f64 + 'static] MapSignal<F>);
+
+impl<F: FnMut(f64) -> f64 + 'static> Signal for MapSignal<F> {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.mapped.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        (self.function)(self.mapped.evaluate())
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        self.mapped.evaluate_block(buffer);
+        for sample in buffer.iter_mut() {
+            *sample = (self.function)(*sample);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Signal that mixes multiple other signals together, each at its own level
+
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+
+/// Mix struct
+pub struct Mix {
+    mixed: Vec<Box<Signal>>,
+    weights: Vec<f64>,  // Level of each mixed signal
+    scratch: Vec<f64>,  // Block buffer for evaluate_block
+}
+
+impl Mix {
+    /// Creates a new Mix signal. There must be one weight for each signal.
+    pub fn new(mixed: Vec<Box<Signal>>, weights: Vec<f64>) -> Mix {
+        assert!(mixed.len() == weights.len(), "Each mixed signal needs exactly one weight");
+        Mix {mixed, weights, scratch: vec![]}
+    }
+
+    /// Creates a new Mix signal with every signal at the same level, scaled so the levels add up
+    /// to 1.0 (i.e. the average of the signals)
+    pub fn new_average(mixed: Vec<Box<Signal>>) -> Mix {
+        let weight = 1.0 / (mixed.len().max(1) as f64);
+        let weights = vec![weight; mixed.len()];
+        Mix::new(mixed, weights)
+    }
+
+    /// Sets the level of the signal at `index`
+    pub fn set_weight(&mut self, index: usize, weight: f64) {
+        self.weights[index] = weight;
+    }
+}
+
+signal_ops!(Mix);
+
+impl Signal for Mix {
+    fn prepare(&mut self, context: &ProcessContext) {
+        for c in &mut self.mixed {
+            c.prepare(context);
+        }
+        self.scratch = vec![0f64; context.block_size];
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let mut output: f64 = 0.0;
+
+        for (c, weight) in self.mixed.iter_mut().zip(self.weights.iter()) {
+            output += c.evaluate() * *weight;
+        }
+
+        output
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        for sample in buffer.iter_mut() {
+            *sample = 0.0;
+        }
+
+        // Evaluate each signal into the scratch buffer, and accumulate them into the output:
+        if self.scratch.len() < buffer.len() {
+            self.scratch.resize(buffer.len(), 0.0);
+        }
+        let scratch = &mut self.scratch[..buffer.len()];
+        for (c, weight) in self.mixed.iter_mut().zip(self.weights.iter()) {
+            c.evaluate_block(scratch);
+            for (sample, mixed) in buffer.iter_mut().zip(scratch.iter()) {
+                *sample += *mixed * *weight;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dsp::generators::{Saw, White};
+
+    fn mix() -> Mix {
+        Mix::new(vec![Box::new(Saw::new(1.0, 441.0, 0.0)), Box::new(White::new(1.0, 0.0, 1)),
+                      Box::new(White::new(0.0, 1.0, 0))],
+                 vec![0.5, 0.25, -1.0])
+    }
+
+    #[test]
+    fn block_matches_evaluate() {
+        let mut by_sample = mix();
+        let mut by_block = mix();
+        by_sample.prepare(&ProcessContext::new(44100.0, 64));
+        by_block.prepare(&ProcessContext::new(44100.0, 64));
+
+        // (Longer than the prepared block size, so the scratch buffer has to grow)
+        let mut buffer = vec![0f64; 100];
+        for _ in 0..3 {
+            by_block.evaluate_block(&mut buffer);
+            for &sample in &buffer {
+                assert!((sample - by_sample.evaluate()).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn average_weights_add_up_to_one() {
+        let signals: Vec<Box<Signal>> = vec![Box::new(White::new(0.0, 1.0, 0)),
+                                             Box::new(White::new(0.0, 2.0, 0)),
+                                             Box::new(White::new(0.0, 6.0, 0))];
+        assert!((Mix::new_average(signals).evaluate() - 3.0).abs() < 1e-12);
+    }
+}
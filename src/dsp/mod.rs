@@ -5,10 +5,45 @@
 //!  - Traits that define different types of signals
 //!  - The ability to negate a signal
 //!  - The ability to add signals together
+//!  - The ability to subtract, multiply (ring modulate), scale, mix and crossfade signals
+//!  - Operators (+, -, *) and combinator methods for writing signal graphs like maths
 //!  - A trait called "Evaluatable" which all signals must use (might rename this to "Signal")
+//!  - Discrete Fourier Transforms (backed by an FFT) and their inverses
+//!  - Window functions to apply before taking a DFT
+//!  - Multi-channel (stereo, etc.) signals, and conversions to and from mono signals
+//!  - A processing context (sample rate, block size) that gets passed to every signal
+//!  - Generator parameters that can be driven by other signals (FM, AM, etc.)
+//!  - Seedable noise generators (white, pink, brown, blue, violet, velvet)
+//!  - Wavetable oscillators, with tables loaded from vectors or WAV files
+//!  - Additive and FM (DX-style operator) synthesis
+//!  - Envelope generators (ADSR, AR, AHDSR, multi-segment) with gate handles
+//!  - Resonant synth filters (state-variable and Moog-style ladder) for fast modulation
+//!  - Filters (biquads, Butterworth/Chebyshev/Bessel/elliptic designs of any order, and FIR
+//!    filters with windowed-sinc, frequency-sampling and equiripple designs)
+//!  - Fast FFT convolution (overlap-add, overlap-save, partitioned) for long FIRs and reverbs
+//!  - Delay lines with fractional, modulatable multi-tap reads and feedback (echo, chorus, etc.)
 
+#[macro_use]
+pub mod ops;
 pub mod generators;
 pub mod traits;
 pub mod add_signals;
 pub mod negate_signal;
-pub mod dft;
\ No newline at end of file
+pub mod multiply_signals;
+pub mod subtract;
+pub mod gain;
+pub mod mix;
+pub mod crossfade;
+pub mod offset;
+pub mod map_signal;
+pub mod clip;
+pub mod dft;
+pub mod window;
+pub mod channels;
+pub mod context;
+pub mod parameter;
+pub mod wav;
+pub mod envelopes;
+pub mod filters;
+pub mod convolution;
+pub mod delay;
\ No newline at end of file
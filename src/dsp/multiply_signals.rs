@@ -0,0 +1,92 @@
+//! Signal that takes multiple other signals and multiplies them together
+//!
+//! Multiplying two audio-rate signals is ring modulation: two sine waves at f1 and f2 multiply out
+//! to two sine waves at (f1 + f2) and (f1 - f2), with neither original frequency left over.
+//! Multiplying by a slow signal (an LFO or envelope) is amplitude modulation / tremolo.
+
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+
+/// MultiplySignals struct
+pub struct MultiplySignals {
+    multiplied: Vec<Box<Signal>>,
+    scratch: Vec<f64>,  // Block buffer for evaluate_block
+}
+
+impl MultiplySignals {
+    /// Creates a new MultiplySignals signal
+    pub fn new(multiplied: Vec<Box<Signal>>) -> MultiplySignals {
+        MultiplySignals {multiplied, scratch: vec![]}
+    }
+}
+
+signal_ops!(MultiplySignals);
+
+impl Signal for MultiplySignals {
+    fn prepare(&mut self, context: &ProcessContext) {
+        for c in &mut self.multiplied {
+            c.prepare(context);
+        }
+        self.scratch = vec![0f64; context.block_size];
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        let mut output: f64 = 1.0;
+
+        for c in &mut self.multiplied {
+            output *= c.evaluate();
+        }
+
+        output
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        for sample in buffer.iter_mut() {
+            *sample = 1.0;
+        }
+
+        // Evaluate each signal into the scratch buffer, and multiply them into the output:
+        if self.scratch.len() < buffer.len() {
+            self.scratch.resize(buffer.len(), 0.0);
+        }
+        let scratch = &mut self.scratch[..buffer.len()];
+        for c in &mut self.multiplied {
+            c.evaluate_block(scratch);
+            for (sample, multiplied) in buffer.iter_mut().zip(scratch.iter()) {
+                *sample *= *multiplied;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dsp::generators::{Saw, White};
+
+    fn product() -> MultiplySignals {
+        MultiplySignals::new(vec![Box::new(Saw::new(1.0, 441.0, 0.0)),
+                                  Box::new(White::new(1.0, 0.0, 1)),
+                                  Box::new(White::new(0.0, -2.0, 0))])
+    }
+
+    #[test]
+    fn block_matches_evaluate() {
+        let mut saw = Saw::new(1.0, 441.0, 0.0);
+        let mut noise = White::new(1.0, 0.0, 1);
+        let mut by_sample = product();
+        let mut by_block = product();
+        let mut buffer = vec![0f64; 100];
+        by_block.evaluate_block(&mut buffer);
+        for &sample in &buffer {
+            let expected = saw.evaluate() * noise.evaluate() * -2.0;
+            assert!((by_sample.evaluate() - expected).abs() < 1e-12);
+            assert!((sample - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn nothing_multiplied_is_one() {
+        assert_eq!(MultiplySignals::new(vec![]).evaluate(), 1.0);
+    }
+}
@@ -1,6 +1,7 @@
 //! Signal that negates the passed-in signal argument
 
 use dsp::traits::Signal;
+use dsp::context::ProcessContext;
 
 /// NegateSignal struct
 pub struct NegateSignal {
@@ -14,8 +15,21 @@ impl NegateSignal {
     }
 }
 
+signal_ops!(NegateSignal);
+
 impl Signal for NegateSignal {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.negated.prepare(context);
+    }
+
     fn evaluate(&mut self) -> f64 {
         -1.0 * self.negated.evaluate()
     }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        self.negated.evaluate_block(buffer);
+        for sample in buffer.iter_mut() {
+            *sample *= -1.0;
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,38 @@
+//! Signal that adds a DC offset to another signal
+
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+use dsp::parameter::Parameter;
+
+/// Offset struct
+pub struct Offset {
+    offset_signal: Box<Signal>,
+    offset: Parameter,  // Amount added to every sample
+}
+
+impl Offset {
+    /// Creates a new Offset signal
+    pub fn new<O: Into<Parameter>>(offset_signal: Box<Signal>, offset: O) -> Offset {
+        Offset {offset_signal, offset: offset.into()}
+    }
+}
+
+signal_ops!(Offset);
+
+impl Signal for Offset {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.offset_signal.prepare(context);
+        self.offset.prepare(context);
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        self.offset_signal.evaluate() + self.offset.evaluate()
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        self.offset_signal.evaluate_block(buffer);
+        for sample in buffer.iter_mut() {
+            *sample += self.offset.evaluate();
+        }
+    }
+}
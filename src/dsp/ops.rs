@@ -0,0 +1,233 @@
+//! Operators and combinator methods for building signal graphs.
+//!
+//! Building graphs out of the combinator structs directly gets verbose fast, so signals also
+//! support the usual arithmetic operators, which build the same nodes behind the scenes:
+//!
+//!  - `a + b` is `AddSignals`, and `a + 0.5` is `Offset`
+//!  - `a - b` is `Subtract`, and `a - 0.5` is `Offset`
+//!  - `a * b` is `MultiplySignals` (ring modulation), and `a * 0.5` is `Gain`
+//!  - `-a` is `NegateSignal`
+//!
+//! and the `SignalExt` methods (`gain`, `gain_db`, `offset`, `map`, `clip`, `boxed`) for
+//! everything else. So a tremolo'd, slightly detuned pair of saw waves can be written as:
+//!
+//! ```ignore
+//! use dsp::ops::SignalExt;
+//! let patch = ((Saw::new(0.5, 220.0, 0.0) + Saw::new(0.5, 221.0, 0.0))
+//!     * Sine::new(0.25, 5.0, 0.75)).clip(-1.0, 1.0);
+//! ```
+//!
+//! Rust only allows operators to be implemented for specific types, so every signal type invokes
+//! `signal_ops!` right next to its `Signal` impl. `Signal` requires the `SignalOps` marker trait
+//! that the macro implements, so a signal type that doesn't invoke it won't compile. Boxed signals
+//! (`Box<Signal>`) get the operators and combinator methods too, without being boxed again.
+
+use dsp::traits::Signal;
+use dsp::parameter::Parameter;
+use dsp::gain::Gain;
+use dsp::offset::Offset;
+use dsp::map_signal::MapSignal;
+use dsp::clip::Clip;
+
+/// Marker for types that have the arithmetic operators (implemented by `signal_ops!`).
+pub trait SignalOps {}
+
+/// Combinator methods available on every signal (and every boxed signal).
+pub trait SignalExt: Sized {
+    /// Boxes the signal up, for passing to combinators (or into a `Vec` of mixed signal types). A
+    /// signal that's already boxed is returned as it is.
+    fn boxed(self) -> Box<Signal>;
+
+    /// Scales the signal by `gain` (a constant or another signal).
+    fn gain<G: Into<Parameter>>(self, gain: G) -> Gain {
+        Gain::new(self.boxed(), gain)
+    }
+
+    /// Scales the signal by `gain_db` decibels (a constant or another signal).
+    fn gain_db<G: Into<Parameter>>(self, gain_db: G) -> Gain {
+        Gain::new_db(self.boxed(), gain_db)
+    }
+
+    /// Adds a DC offset (a constant or another signal) to the signal.
+    fn offset<O: Into<Parameter>>(self, offset: O) -> Offset {
+        Offset::new(self.boxed(), offset)
+    }
+
+    /// Passes every sample through `function`.
+    fn map<F: FnMut(f64) -> f64>(self, function: F) -> MapSignal<F> {
+        MapSignal::new(self.boxed(), function)
+    }
+
+    /// Hard-clips the signal to the range `low` to `high`.
+    fn clip(self, low: f64, high: f64) -> Clip {
+        Clip::new(self.boxed(), low, high)
+    }
+}
+
+impl<S: Signal + 'static> SignalExt for S {
+    fn boxed(self) -> Box<Signal> {
+        Box::new(self)
+    }
+}
+
+impl SignalExt for Box<Signal> {
+    fn boxed(self) -> Box<Signal> {
+        self
+    }
+}
+
+/// Implements the arithmetic operators (and `SignalOps`) for a signal type, optionally with generic
+/// parameters in square brackets before the type:
+///
+/// ```ignore
+/// signal_ops!(Sine);
+/// signal_ops!([F: FnMut(f64) -> f64 + 'static] MapSignal<F>);
+/// ```
+macro_rules! signal_ops {
+    (@operators [$($generics:tt)*] $signal:ty) => {
+        impl<R: ::dsp::traits::Signal + 'static, $($generics)*> ::std::ops::Add<R> for $signal {
+            type Output = ::dsp::add_signals::AddSignals;
+            fn add(self, other: R) -> ::dsp::add_signals::AddSignals {
+                ::dsp::add_signals::AddSignals::new(vec![::dsp::ops::SignalExt::boxed(self),
+                                                         Box::new(other)])
+            }
+        }
+
+        impl<$($generics)*> ::std::ops::Add<Box<::dsp::traits::Signal>> for $signal {
+            type Output = ::dsp::add_signals::AddSignals;
+            fn add(self, other: Box<::dsp::traits::Signal>) -> ::dsp::add_signals::AddSignals {
+                ::dsp::add_signals::AddSignals::new(vec![::dsp::ops::SignalExt::boxed(self), other])
+            }
+        }
+
+        impl<$($generics)*> ::std::ops::Add<f64> for $signal {
+            type Output = ::dsp::offset::Offset;
+            fn add(self, other: f64) -> ::dsp::offset::Offset {
+                ::dsp::offset::Offset::new(::dsp::ops::SignalExt::boxed(self), other)
+            }
+        }
+
+        impl<$($generics)*> ::std::ops::Add<$signal> for f64 {
+            type Output = ::dsp::offset::Offset;
+            fn add(self, other: $signal) -> ::dsp::offset::Offset {
+                ::dsp::offset::Offset::new(::dsp::ops::SignalExt::boxed(other), self)
+            }
+        }
+
+        impl<R: ::dsp::traits::Signal + 'static, $($generics)*> ::std::ops::Sub<R> for $signal {
+            type Output = ::dsp::subtract::Subtract;
+            fn sub(self, other: R) -> ::dsp::subtract::Subtract {
+                ::dsp::subtract::Subtract::new(::dsp::ops::SignalExt::boxed(self), Box::new(other))
+            }
+        }
+
+        impl<$($generics)*> ::std::ops::Sub<Box<::dsp::traits::Signal>> for $signal {
+            type Output = ::dsp::subtract::Subtract;
+            fn sub(self, other: Box<::dsp::traits::Signal>) -> ::dsp::subtract::Subtract {
+                ::dsp::subtract::Subtract::new(::dsp::ops::SignalExt::boxed(self), other)
+            }
+        }
+
+        impl<$($generics)*> ::std::ops::Sub<f64> for $signal {
+            type Output = ::dsp::offset::Offset;
+            fn sub(self, other: f64) -> ::dsp::offset::Offset {
+                ::dsp::offset::Offset::new(::dsp::ops::SignalExt::boxed(self), -other)
+            }
+        }
+
+        impl<$($generics)*> ::std::ops::Sub<$signal> for f64 {
+            type Output = ::dsp::offset::Offset;
+            fn sub(self, other: $signal) -> ::dsp::offset::Offset {
+                let negated = ::dsp::negate_signal::NegateSignal::new(
+                    ::dsp::ops::SignalExt::boxed(other));
+                ::dsp::offset::Offset::new(Box::new(negated), self)
+            }
+        }
+
+        impl<R: ::dsp::traits::Signal + 'static, $($generics)*> ::std::ops::Mul<R> for $signal {
+            type Output = ::dsp::multiply_signals::MultiplySignals;
+            fn mul(self, other: R) -> ::dsp::multiply_signals::MultiplySignals {
+                ::dsp::multiply_signals::MultiplySignals::new(
+                    vec![::dsp::ops::SignalExt::boxed(self), Box::new(other)])
+            }
+        }
+
+        impl<$($generics)*> ::std::ops::Mul<Box<::dsp::traits::Signal>> for $signal {
+            type Output = ::dsp::multiply_signals::MultiplySignals;
+            fn mul(self, other: Box<::dsp::traits::Signal>)
+                -> ::dsp::multiply_signals::MultiplySignals {
+                ::dsp::multiply_signals::MultiplySignals::new(
+                    vec![::dsp::ops::SignalExt::boxed(self), other])
+            }
+        }
+
+        impl<$($generics)*> ::std::ops::Mul<f64> for $signal {
+            type Output = ::dsp::gain::Gain;
+            fn mul(self, other: f64) -> ::dsp::gain::Gain {
+                ::dsp::gain::Gain::new(::dsp::ops::SignalExt::boxed(self), other)
+            }
+        }
+
+        impl<$($generics)*> ::std::ops::Mul<$signal> for f64 {
+            type Output = ::dsp::gain::Gain;
+            fn mul(self, other: $signal) -> ::dsp::gain::Gain {
+                ::dsp::gain::Gain::new(::dsp::ops::SignalExt::boxed(other), self)
+            }
+        }
+
+        impl<$($generics)*> ::std::ops::Neg for $signal {
+            type Output = ::dsp::negate_signal::NegateSignal;
+            fn neg(self) -> ::dsp::negate_signal::NegateSignal {
+                ::dsp::negate_signal::NegateSignal::new(::dsp::ops::SignalExt::boxed(self))
+            }
+        }
+    };
+    ([$($generics:tt)*] $signal:ty) => {
+        impl<$($generics)*> ::dsp::ops::SignalOps for $signal {}
+        signal_ops!(@operators [$($generics)*] $signal);
+    };
+    ($signal:ty) => {
+        signal_ops!([] $signal);
+    };
+}
+
+// Boxed signals aren't `Signal`s themselves (so they never get boxed twice), but they get the
+// operators too:
+signal_ops!(@operators [] Box<Signal>);
+
+#[cfg(test)]
+mod tests {
+    use dsp::ops::SignalExt;
+    use dsp::parameter::Parameter;
+    use dsp::traits::Signal;
+    use dsp::generators::White;
+
+    /// Address of a boxed signal's contents.
+    fn address(signal: &Signal) -> *const u8 {
+        signal as *const Signal as *const u8
+    }
+
+    #[test]
+    fn boxed_signals_are_not_boxed_again() {
+        let signal: Box<Signal> = Box::new(White::new(1.0, 0.0, 1));
+        let before = address(&*signal);
+        let signal = signal.boxed();
+        assert_eq!(address(&*signal), before);
+
+        match Parameter::from(signal) {
+            Parameter::Modulated(signal) => assert_eq!(address(&*signal), before),
+            Parameter::Constant(_) => panic!("a signal should make a modulated parameter"),
+        }
+    }
+
+    #[test]
+    fn operators_match_evaluating_by_hand() {
+        let mut a = White::new(1.0, 0.0, 1);
+        let mut b = White::new(1.0, 0.0, 2);
+        let mut patch = -(White::new(1.0, 0.0, 1) * 0.5 + White::new(1.0, 0.0, 2).boxed()) - 0.25;
+        for _ in 0..100 {
+            let expected = -(a.evaluate() * 0.5 + b.evaluate()) - 0.25;
+            assert_eq!(patch.evaluate(), expected);
+        }
+    }
+}
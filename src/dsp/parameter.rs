@@ -0,0 +1,66 @@
+//! Generator parameters that can be either a fixed value or another signal.
+//!
+//! This is what lets generators accept OTHER signals as inputs: anywhere a generator takes a
+//! `Parameter`, you can pass either a plain `f64` (which stays constant) or a signal (which gets
+//! evaluated once per sample). For example, a Triangle wave with its frequency wobbling between
+//! 430Hz and 450Hz five times per second:
+//!
+//! ```ignore
+//! let lfo = Sine::new(10.0, 5.0, 440.0);
+//! let vibrato = Triangle::new(0.5, lfo, 0.0);
+//! ```
+
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+
+/// Parameter enum
+pub enum Parameter {
+    /// A value that never changes
+    Constant(f64),
+    /// A value that follows the output of another signal, sample by sample
+    Modulated(Box<Signal>),
+}
+
+impl Parameter {
+    /// Gets the parameter's signal (if it has one) ready to run with the given settings.
+    pub fn prepare(&mut self, context: &ProcessContext) {
+        if let Parameter::Modulated(ref mut signal) = *self {
+            signal.prepare(context);
+        }
+    }
+
+    /// Value of the parameter for the next sample.
+    pub fn evaluate(&mut self) -> f64 {
+        match *self {
+            Parameter::Constant(value) => value,
+            Parameter::Modulated(ref mut signal) => signal.evaluate(),
+        }
+    }
+
+    /// The parameter's value, if it's a constant. Generators use this to pick a fast path for
+    /// unmodulated parameters.
+    pub fn constant(&self) -> Option<f64> {
+        match *self {
+            Parameter::Constant(value) => Some(value),
+            Parameter::Modulated(_) => None,
+        }
+    }
+}
+
+impl From<f64> for Parameter {
+    fn from(value: f64) -> Parameter {
+        Parameter::Constant(value)
+    }
+}
+
+impl From<Box<Signal>> for Parameter {
+    fn from(signal: Box<Signal>) -> Parameter {
+        Parameter::Modulated(signal)
+    }
+}
+
+impl<S: Signal + 'static> From<S> for Parameter {
+    fn from(signal: S) -> Parameter {
+        Parameter::Modulated(Box::new(signal))
+    }
+}
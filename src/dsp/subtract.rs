@@ -0,0 +1,69 @@
+//! Signal that subtracts one signal from another
+
+use dsp::traits::Signal;
+use dsp::context::ProcessContext;
+
+/// Subtract struct
+pub struct Subtract {
+    minuend: Box<Signal>,     // Signal being subtracted from
+    subtrahend: Box<Signal>,  // Signal being subtracted
+    scratch: Vec<f64>,        // Block buffer for evaluate_block
+}
+
+impl Subtract {
+    /// Creates a new Subtract signal (`minuend - subtrahend`)
+    pub fn new(minuend: Box<Signal>, subtrahend: Box<Signal>) -> Subtract {
+        Subtract {minuend, subtrahend, scratch: vec![]}
+    }
+}
+
+signal_ops!(Subtract);
+
+impl Signal for Subtract {
+    fn prepare(&mut self, context: &ProcessContext) {
+        self.minuend.prepare(context);
+        self.subtrahend.prepare(context);
+        self.scratch = vec![0f64; context.block_size];
+    }
+
+    fn evaluate(&mut self) -> f64 {
+        self.minuend.evaluate() - self.subtrahend.evaluate()
+    }
+
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        if self.scratch.len() < buffer.len() {
+            self.scratch.resize(buffer.len(), 0.0);
+        }
+        let scratch = &mut self.scratch[..buffer.len()];
+        self.minuend.evaluate_block(buffer);
+        self.subtrahend.evaluate_block(scratch);
+        for (sample, subtracted) in buffer.iter_mut().zip(scratch.iter()) {
+            *sample -= *subtracted;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dsp::generators::{Saw, White};
+
+    fn difference() -> Subtract {
+        Subtract::new(Box::new(Saw::new(1.0, 441.0, 0.0)), Box::new(White::new(1.0, 0.0, 1)))
+    }
+
+    #[test]
+    fn block_matches_evaluate() {
+        let mut saw = Saw::new(1.0, 441.0, 0.0);
+        let mut noise = White::new(1.0, 0.0, 1);
+        let mut by_sample = difference();
+        let mut by_block = difference();
+        let mut buffer = vec![0f64; 100];
+        by_block.evaluate_block(&mut buffer);
+        for &sample in &buffer {
+            let expected = saw.evaluate() - noise.evaluate();
+            assert!((by_sample.evaluate() - expected).abs() < 1e-12);
+            assert!((sample - expected).abs() < 1e-12);
+        }
+    }
+}
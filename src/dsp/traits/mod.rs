@@ -1,5 +1,8 @@
 //! DSP traits
 
+use dsp::context::ProcessContext;
+use dsp::ops::SignalOps;
+
 /// Signal trait
 ///
 /// All signals must implement this trait. This allows us to do some pretty powerful stuff, like:
@@ -8,11 +11,84 @@
 ///  - Make generators that generate signals, such as Sine waves, Square waves, Saw waves, etc.
 ///  - Allow generators to accept OTHER signals as inputs to control the frequency, amplitude, etc.
 ///
-/// Note that this type of Signal is single-channel only.
-/// TODO: create a trait for double-channel (stereo), and have some sort of conversion between them
+/// Note that this type of Signal is single-channel only. See `MultichannelSignal` for stereo and
+/// other multi-channel signals, and the `dsp::channels` module for converting between the two.
+///
+/// Every signal type also has to invoke `signal_ops!` (which implements `SignalOps`), so it gets
+/// the arithmetic operators from `dsp::ops`.
+pub trait Signal: SignalOps {
+    /// Gets the signal ready to run with the given sample rate and block size.
+    ///
+    /// This must be called before the first sample is evaluated (and again whenever the settings
+    /// change). Signals that depend on the sample rate recompute whatever they need to, and signals
+    /// built out of other signals must pass the context on to all of them (there's no default, so
+    /// that a new signal can't forget to).
+    fn prepare(&mut self, context: &ProcessContext);
 
-pub trait Signal {
     /// When requested, all signals must produce a f64 sample.
     /// Note that PortAudio does not accept f64s; it will downsample to f32 for output.
     fn evaluate(&mut self) -> f64;
-}
\ No newline at end of file
+
+    /// Fills `buffer` with the next `buffer.len()` samples of the signal.
+    ///
+    /// This is the same as calling `evaluate` once per sample (and that is exactly what the
+    /// default implementation does), but signals can override it with a faster version that
+    /// avoids a virtual call per sample and hoists per-sample work out of the loop.
+    fn evaluate_block(&mut self, buffer: &mut [f64]) {
+        for sample in buffer.iter_mut() {
+            *sample = self.evaluate();
+        }
+    }
+}
+
+/// Oscillator trait
+///
+/// Periodic generators (Sine, Saw, etc.) implement this on top of `Signal`, so their phase can be
+/// controlled from outside: set where in the period they start, jump back there (retrigger), or
+/// get reset by a master oscillator (hard sync, see `dsp::generators::HardSync`).
+///
+/// Phases are fractions of the whole period, from 0.0 up to (but not including) 1.0.
+pub trait Oscillator: Signal {
+    /// Sets the phase the oscillator starts at (and returns to on `reset`), and jumps to it.
+    fn set_phase(&mut self, phase: f64);
+
+    /// Jumps back to the phase set by `set_phase` (0.0 unless it was changed).
+    fn reset(&mut self);
+
+    /// Evaluates the next sample exactly like `evaluate`, then resets the phase partway through
+    /// the following sample period: `fraction` (0.0 to 1.0) is how far through that period the
+    /// reset happens.
+    ///
+    /// Returns the sample, and the size of the jump (in output units) the reset caused, so the
+    /// caller can smooth it out.
+    fn evaluate_synced(&mut self, fraction: f64) -> (f64, f64);
+}
+
+/// Multi-channel signal trait
+///
+/// The multi-channel counterpart of `Signal`: instead of a single sample, every evaluation
+/// produces a "frame" holding one sample for each channel (for stereo, channel 0 is left and
+/// channel 1 is right).
+pub trait MultichannelSignal {
+    /// Gets the signal ready to run with the given sample rate and block size (see
+    /// `Signal::prepare`). Here, the block size counts frames rather than samples.
+    fn prepare(&mut self, context: &ProcessContext);
+
+    /// Number of channels in each frame. This must not change over the lifetime of the signal.
+    fn channels(&self) -> usize;
+
+    /// When requested, all multi-channel signals must fill `frame` (which is exactly `channels()`
+    /// samples long) with the next sample of every channel.
+    fn evaluate_frame(&mut self, frame: &mut [f64]);
+
+    /// Fills `buffer` with the next `buffer.len() / channels()` frames of the signal, interleaved
+    /// (all the channels of frame 0, then all the channels of frame 1, etc.).
+    ///
+    /// The default implementation calls `evaluate_frame` once per frame.
+    fn evaluate_frames(&mut self, buffer: &mut [f64]) {
+        let channels = self.channels();
+        for frame in buffer.chunks_mut(channels) {
+            self.evaluate_frame(frame);
+        }
+    }
+}
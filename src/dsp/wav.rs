@@ -0,0 +1,108 @@
+//! WAV file loading (for wavetables, impulse responses, etc.)
+//!
+//! Integer samples of any bit depth get scaled to the range -1.0 to 1.0, and floating point
+//! samples are used as-is.
+
+use std::path::Path;
+use hound;
+
+/// Audio read from a WAV file.
+pub struct WavFile {
+    /// Sample rate the file was recorded at (in Hz)
+    pub sample_rate: f64,
+    /// Samples for each channel (one `Vec` per channel, all the same length)
+    pub channels: Vec<Vec<f64>>,
+}
+
+impl WavFile {
+    /// Reads a WAV file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<WavFile, hound::Error> {
+        let mut reader = try!(hound::WavReader::open(path));
+        let spec = reader.spec();
+
+        // Read the (interleaved) samples
+        let samples: Vec<f64> = match spec.sample_format {
+            hound::SampleFormat::Float => {
+                try!(reader.samples::<f32>().map(|s| s.map(|s| s as f64)).collect())
+            },
+            hound::SampleFormat::Int => {
+                let scale = 1.0 / ((1u64 << (spec.bits_per_sample - 1)) as f64);
+                try!(reader.samples::<i32>().map(|s| s.map(|s| s as f64 * scale)).collect())
+            },
+        };
+
+        // Split them up into channels
+        let num_channels = spec.channels as usize;
+        let mut channels = vec![Vec::with_capacity(samples.len() / num_channels); num_channels];
+        for frame in samples.chunks(num_channels) {
+            for (channel, sample) in channels.iter_mut().zip(frame) {
+                channel.push(*sample);
+            }
+        }
+
+        Ok(WavFile {
+            sample_rate: spec.sample_rate as f64,
+            channels,
+        })
+    }
+
+    /// Number of samples in each channel.
+    pub fn len(&self) -> usize {
+        self.channels.first().map_or(0, |channel| channel.len())
+    }
+
+    /// Whether the file has no samples at all.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// All the channels averaged together into one.
+    pub fn mono(&self) -> Vec<f64> {
+        let scale = 1.0 / (self.channels.len().max(1) as f64);
+        (0..self.len())
+            .map(|i| self.channels.iter().map(|channel| channel[i]).sum::<f64>() * scale)
+            .collect()
+    }
+}
+
+/// WAV writing for the file loaders' tests.
+#[cfg(test)]
+pub mod tests {
+    use std::env;
+    use std::path::PathBuf;
+    use hound;
+
+    /// Writes `channels` to a 32-bit float WAV file called `name` in the temp directory, and
+    /// returns its path.
+    pub fn write_temp(name: &str, sample_rate: u32, channels: &[Vec<f64>]) -> PathBuf {
+        let path = env::temp_dir().join(name);
+        let spec = hound::WavSpec {
+            channels: channels.len() as u16,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..channels[0].len() {
+            for channel in channels {
+                writer.write_sample(channel[i] as f32).unwrap();
+            }
+        }
+        writer.finalize().unwrap();
+        path
+    }
+
+    #[test]
+    fn reads_back_what_was_written() {
+        let left: Vec<f64> = (0..100).map(|i| i as f64 / 100.0).collect();
+        let right: Vec<f64> = left.iter().map(|x| -x).collect();
+        let path = write_temp("wav_reads_back_what_was_written.wav", 48000, &[left.clone(), right]);
+        let file = super::WavFile::open(&path).unwrap();
+        assert_eq!(file.sample_rate, 48000.0);
+        assert_eq!(file.len(), 100);
+        assert!(file.mono().iter().all(|&x| x.abs() < 1e-7));
+        for (read, written) in file.channels[0].iter().zip(&left) {
+            assert!((read - written).abs() < 1e-7);
+        }
+    }
+}
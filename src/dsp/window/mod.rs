@@ -0,0 +1,204 @@
+//! Window functions for spectral analysis.
+//!
+//! The DFT assumes that the block of samples it's given repeats forever. Unless the block happens
+//! to contain a whole number of periods, the ends don't line up, and that discontinuity smears
+//! ("leaks") energy from every frequency into all the bins around it. Multiplying the block by a
+//! window that tapers off to (nearly) zero at both ends hides the discontinuity, at the cost of a
+//! wider main lobe for each frequency.
+//!
+//! Windows come in two flavours:
+//!  - Periodic windows are what you want before a DFT (this is what `apply` uses).
+//!  - Symmetric windows are what you want for filter design (e.g. windowed-sinc FIR filters).
+
+use std::f64;
+
+/// Window function type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Window {
+    /// No window at all (every sample multiplied by 1.0)
+    Rectangular,
+    /// Hann (raised cosine) window
+    Hann,
+    /// Hamming window
+    Hamming,
+    /// Classic 3-term Blackman window
+    Blackman,
+    /// 4-term Blackman-Harris window (-92dB sidelobes)
+    BlackmanHarris,
+    /// Flat-top window (very accurate amplitudes, very wide main lobe)
+    FlatTop,
+    /// Kaiser window with the given beta (0.0 or more; larger beta = lower sidelobes, wider main
+    /// lobe)
+    Kaiser(f64),
+    /// Tukey (tapered cosine) window, with the given fraction (0.0 to 1.0) of the window tapered
+    Tukey(f64),
+    /// Gaussian window with the given standard deviation (as a fraction of half the window length,
+    /// more than 0.0)
+    Gaussian(f64),
+}
+
+impl Window {
+    /// Window coefficients for a symmetric window of length `len` (for filter design).
+    pub fn symmetric(&self, len: usize) -> Vec<f64> {
+        self.check_parameter();
+        if len == 1 {
+            return vec![1.0];
+        }
+        (0..len).map(|n| self.value(n as f64, (len - 1) as f64)).collect()
+    }
+
+    /// Window coefficients for a periodic window of length `len` (for spectral analysis).
+    pub fn periodic(&self, len: usize) -> Vec<f64> {
+        self.check_parameter();
+        (0..len).map(|n| self.value(n as f64, len as f64)).collect()
+    }
+
+    /// Multiplies `signal` by the (periodic) window, ready to be passed to a DFT function.
+    pub fn apply(&self, signal: Vec<f64>) -> Vec<f64> {
+        let coefficients = self.periodic(signal.len());
+        signal.iter().zip(coefficients.iter()).map(|(x, w)| x * w).collect()
+    }
+
+    /// Coherent gain of the window: the factor a sinusoid's DFT magnitude gets scaled by.
+    ///
+    /// Divide windowed DFT magnitudes by this to get back the amplitudes an unwindowed DFT of a
+    /// perfectly periodic signal would report.
+    pub fn coherent_gain(&self, len: usize) -> f64 {
+        let coefficients = self.periodic(len);
+        coefficients.iter().sum::<f64>() / (len as f64)
+    }
+
+    /// Equivalent noise bandwidth of the window, in DFT bins.
+    ///
+    /// This is how much wider than a single bin a rectangular filter would have to be to let
+    /// through the same amount of white noise power as the window does.
+    pub fn enbw(&self, len: usize) -> f64 {
+        let coefficients = self.periodic(len);
+        let sum: f64 = coefficients.iter().sum();
+        let sum_of_squares: f64 = coefficients.iter().map(|w| w * w).sum();
+        (len as f64) * sum_of_squares / (sum * sum)
+    }
+
+    /// Panics if the window's parameter (if it has one) is out of range.
+    fn check_parameter(&self) {
+        match *self {
+            Window::Kaiser(beta) => assert!(beta >= 0.0, "Kaiser beta must be 0.0 or more"),
+            Window::Tukey(alpha) => assert!((0.0..=1.0).contains(&alpha),
+                                            "Tukey alpha must be between 0.0 and 1.0"),
+            Window::Gaussian(sigma) => assert!(sigma > 0.0, "Gaussian sigma must be more than 0.0"),
+            _ => {},
+        }
+    }
+
+    /// Value of the window at position `n` out of `m` (0 <= n <= m).
+    fn value(&self, n: f64, m: f64) -> f64 {
+        match *self {
+            Window::Rectangular => 1.0,
+            Window::Hann => cosine_sum(&[0.5, 0.5], n, m),
+            Window::Hamming => cosine_sum(&[0.54, 0.46], n, m),
+            Window::Blackman => cosine_sum(&[0.42, 0.5, 0.08], n, m),
+            Window::BlackmanHarris => cosine_sum(&[0.35875, 0.48829, 0.14128, 0.01168], n, m),
+            Window::FlatTop => cosine_sum(
+                &[0.21557895, 0.41663158, 0.277263158, 0.083578947, 0.006947368], n, m
+            ),
+            Window::Kaiser(beta) => {
+                let x = 2.0 * n / m - 1.0;
+                bessel_i0(beta * (1.0 - x * x).max(0.0).sqrt()) / bessel_i0(beta)
+            },
+            Window::Tukey(alpha) => {
+                // Cosine taper over the first and last (alpha / 2) of the window, flat in between
+                let position = n / m;
+                let taper = alpha / 2.0;
+                if alpha <= 0.0 {
+                    1.0
+                }
+                else if position < taper {
+                    0.5 * (1.0 - (f64::consts::PI * position / taper).cos())
+                }
+                else if position > 1.0 - taper {
+                    0.5 * (1.0 - (f64::consts::PI * (1.0 - position) / taper).cos())
+                }
+                else {
+                    1.0
+                }
+            },
+            Window::Gaussian(sigma) => {
+                let x = (n - m / 2.0) / (sigma * m / 2.0);
+                (-0.5 * x * x).exp()
+            },
+        }
+    }
+}
+
+/// Generalized cosine window: a0 - a1*cos(2*pi*n/m) + a2*cos(4*pi*n/m) - ...
+fn cosine_sum(coefficients: &[f64], n: f64, m: f64) -> f64 {
+    let mut output = 0.0;
+    let mut sign = 1.0;
+    for (k, a) in coefficients.iter().enumerate() {
+        output += sign * a * (2.0 * f64::consts::PI * (k as f64) * n / m).cos();
+        sign = -sign;
+    }
+    output
+}
+
+/// Zeroth-order modified Bessel function of the first kind (used by the Kaiser window).
+fn bessel_i0(x: f64) -> f64 {
+    // Power series: sum of ((x/2)^k / k!)^2, which converges quickly for the betas people use
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half_x = x / 2.0;
+    let mut k = 1.0;
+    while term > sum * 1e-16 {
+        term *= (half_x / k) * (half_x / k);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use dsp::window::Window;
+
+    #[test]
+    #[should_panic]
+    fn gaussian_needs_positive_sigma() {
+        Window::Gaussian(0.0).periodic(16);
+    }
+
+    #[test]
+    #[should_panic]
+    fn tukey_needs_alpha_up_to_one() {
+        Window::Tukey(1.5).symmetric(16);
+    }
+
+    #[test]
+    #[should_panic]
+    fn kaiser_needs_non_negative_beta() {
+        Window::Kaiser(-1.0).periodic(16);
+    }
+
+    #[test]
+    fn parameter_range_ends_are_allowed() {
+        assert!(Window::Tukey(0.0).periodic(16).iter().all(|&w| w == 1.0));
+        assert!(Window::Tukey(1.0).symmetric(16).iter().all(|w| w.is_finite()));
+        assert!(Window::Kaiser(0.0).periodic(16).iter().all(|&w| w == 1.0));
+    }
+
+    #[test]
+    fn coherent_gain_and_enbw_match_the_known_values() {
+        // (window, coherent gain, ENBW in bins)
+        let known = [
+            (Window::Rectangular, 1.0, 1.0),
+            (Window::Hann, 0.5, 1.5),
+            (Window::Hamming, 0.54, 1.3628),
+            (Window::Blackman, 0.42, 1.7268),
+            (Window::BlackmanHarris, 0.35875, 2.0044),
+            (Window::FlatTop, 0.21557895, 3.7703),
+        ];
+        for &(window, coherent_gain, enbw) in &known {
+            assert!((window.coherent_gain(1024) - coherent_gain).abs() < 1e-9, "{:?}", window);
+            assert!((window.enbw(1024) - enbw).abs() < 1e-4, "{:?}: {}", window, window.enbw(1024));
+        }
+    }
+}
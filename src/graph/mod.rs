@@ -2,34 +2,63 @@
 //!
 //! Send samples here, get graph of points
 
-use chan::Receiver;
+use ringbuf::HeapCons;
+use ringbuf::traits::Consumer;
 use itertools_num::linspace;
 use criterion_plot::prelude::*;
 use std::path::Path;
 use std::string::String;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::{thread, time};
 use dsp;
+use dsp::window::Window;
+use dsp::context::ProcessContext;
 
 // Constants:
-use audio_playground::SAMPLE_RATE;
+const SNAPSHOT_SECONDS: f64 = 0.1;      // Length of the audio/magnitude/phase plots
+const SPECTROGRAM_SECONDS: f64 = 2.0;   // Length of the spectrogram
+const STFT_FRAME_SIZE: usize = 2048;
+const STFT_HOP_SIZE: usize = 512;
+const SPECTROGRAM_DB_RANGE: f64 = 100.0;  // Anything this far below the loudest bin is black
 
 /// Grapher
 pub struct Grapher {
 }
 
 /// "Run" the grapher
-/// Probably want to run this in a separate thread and send samples over a channel.
-pub fn run(recv_points: Receiver<f64>) {
-    // Get 0.1 seconds' worth of samples, and then plot it:
-    let points: Vec<f64> = recv_points.iter().take((SAMPLE_RATE * 0.1) as usize).collect();
+/// Probably want to run this in a separate thread and push samples through a ring buffer.
+///
+/// Returns once it has plotted everything, dropping `recv_points` (whatever is pushing to it should
+/// just drop samples once it fills up).
+///
+/// The samples are expected at `context.sample_rate`. `window` is applied to the samples before
+/// taking the DFT, to cut down on spectral leakage.
+pub fn run(context: ProcessContext, mut recv_points: HeapCons<f64>, window: Window) {
+    // Get enough samples for the spectrogram, and plot the first 0.1 seconds' worth directly:
+    let mut all_points = vec![0f64; (context.sample_rate * SPECTROGRAM_SECONDS) as usize];
+    let mut received = recv_points.pop_slice(&mut all_points);
+    while received < all_points.len() {
+        thread::sleep(time::Duration::from_millis(10));
+        received += recv_points.pop_slice(&mut all_points[received..]);
+    }
+    let points: Vec<f64> = all_points.iter()
+        .take((context.sample_rate * SNAPSHOT_SECONDS) as usize)
+        .cloned()
+        .collect();
     plot_vector(points.clone(), "audio", "audio.svg", false);
 
-    let (magnitude, phase) = dsp::dft::vec_to_polar(points);
+    // Window the samples, and undo the window's gain so the magnitudes stay comparable between
+    // different windows:
+    let gain = window.coherent_gain(points.len());
+    let (magnitude, phase) = dsp::dft::vec_to_polar(window.apply(points));
+    let magnitude = magnitude.iter().map(|m| m / gain).collect();
     plot_vector(magnitude, "magnitude", "magnitude.svg", true);
+    plot_vector(dsp::dft::unwrap_phase(phase), "phase", "phase.svg", false);
 
-    // TODO: Just close the channel instead of constantly recv-ing here
-    loop {
-        recv_points.recv();
-    }
+    let stft = dsp::dft::Stft::new(STFT_FRAME_SIZE, STFT_HOP_SIZE, window, STFT_FRAME_SIZE);
+    plot_spectrogram(stft.spectrogram(&all_points), &stft, context.sample_rate,
+                     "spectrogram", "spectrogram.svg");
 }
 
 /// Plot an arbitrary vector.
@@ -80,4 +109,104 @@ pub fn plot_vector(y_values: Vec<f64>, dataname: &'static str, filename: &'stati
                 .ok()
                 .and_then(|p| String::from_utf8(p.stderr).ok())
         }).expect("ERROR occurred while plotting");
-}
\ No newline at end of file
+}
+
+/// Plot a spectrogram (as produced by `Stft::spectrogram`) as a time-frequency heat map.
+///
+/// Time runs along the x axis and frequency (in Hz, given the `sample_rate` the STFT was taken at)
+/// up the y axis. Magnitudes are drawn on a dB scale relative to the loudest bin, from black
+/// (quiet) through red and yellow to white (loud).
+///
+/// criterion-plot can't draw heat maps, so this writes the .svg file directly.
+pub fn plot_spectrogram(magnitudes: Vec<Vec<f64>>, stft: &dsp::dft::Stft, sample_rate: f64,
+                        dataname: &'static str, filename: &'static str) {
+    // Layout of the figure (same size as the plot_vector plots):
+    let (width, height) = (1336.0, 768.0);
+    let (left, right, top, bottom) = (90.0, 130.0, 40.0, 60.0);
+    let plot_width = width - left - right;
+    let plot_height = height - top - bottom;
+
+    let frames = magnitudes.len();
+    let bins = stft.fft_size() / 2 + 1;
+    let duration = (frames * stft.hop_size()) as f64 / sample_rate;
+    let nyquist = sample_rate / 2.0;
+    let cell_width = plot_width / (frames.max(1) as f64);
+    let cell_height = plot_height / (bins as f64);
+
+    // Everything is relative to the loudest bin:
+    let max_magnitude = magnitudes.iter()
+        .flat_map(|frame| frame.iter())
+        .fold(1e-12f64, |max, &m| max.max(m));
+    let level = |m: f64| -> usize {
+        // Quantize to 64 colour levels so neighbouring bins can share a rectangle
+        let db = 20.0 * (m.max(1e-300) / max_magnitude).log10();
+        let normalized = ((db + SPECTROGRAM_DB_RANGE) / SPECTROGRAM_DB_RANGE).clamp(0.0, 1.0);
+        (normalized * 63.0).round() as usize
+    };
+
+    let mut svg = String::new();
+    svg.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         font-family=\"Helvetica\" font-size=\"16\">\n", width, height));
+    svg.push_str(&format!("<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n", width, height));
+    svg.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"black\"/>\n",
+                          left, top, plot_width, plot_height));
+
+    // The cells themselves (runs of bins with the same colour get merged into one rectangle):
+    for (t, frame) in magnitudes.iter().enumerate() {
+        let x = left + (t as f64) * cell_width;
+        let mut k = 0;
+        while k < bins {
+            let run_level = level(frame[k]);
+            let mut run_end = k + 1;
+            while run_end < bins && level(frame[run_end]) == run_level {
+                run_end += 1;
+            }
+            if run_level > 0 {
+                let y = top + plot_height - (run_end as f64) * cell_height;
+                svg.push_str(&format!(
+                    "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"/>\n",
+                    x, y, cell_width + 0.5, ((run_end - k) as f64) * cell_height + 0.5,
+                    heat_colour(run_level as f64 / 63.0)));
+            }
+            k = run_end;
+        }
+    }
+
+    // Axes, ticks and labels:
+    for i in 0..6 {
+        let fraction = (i as f64) / 5.0;
+        let y = top + plot_height * (1.0 - fraction);
+        let x = left + plot_width * fraction;
+        svg.push_str(&format!("<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{:.0} Hz</text>\n",
+                              left - 8.0, y + 5.0, nyquist * fraction));
+        svg.push_str(&format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{:.2} s</text>\n",
+                              x, top + plot_height + 22.0, duration * fraction));
+    }
+    svg.push_str(&format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+                          left + plot_width / 2.0, top - 12.0, dataname));
+
+    // Colour bar:
+    let bar_x = left + plot_width + 30.0;
+    for i in 0..64 {
+        let y = top + plot_height * (1.0 - ((i + 1) as f64) / 64.0);
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{:.2}\" width=\"20\" height=\"{:.2}\" fill=\"{}\"/>\n",
+            bar_x, y, plot_height / 64.0 + 0.5, heat_colour((i as f64) / 63.0)));
+    }
+    svg.push_str(&format!("<text x=\"{}\" y=\"{}\">0 dB</text>\n", bar_x + 26.0, top + 5.0));
+    svg.push_str(&format!("<text x=\"{}\" y=\"{}\">-{} dB</text>\n",
+                          bar_x + 26.0, top + plot_height + 5.0, SPECTROGRAM_DB_RANGE));
+    svg.push_str("</svg>\n");
+
+    // Spit out the plot to a .svg file:
+    File::create(Path::new(filename))
+        .and_then(|file| BufWriter::new(file).write_all(svg.as_bytes()))
+        .expect("ERROR occurred while plotting");
+}
+
+/// Map a value from 0.0 to 1.0 onto a black-red-yellow-white colour scale.
+fn heat_colour(value: f64) -> String {
+    let channel = |start: f64| (((value - start) * 3.0).clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(0.0), channel(1.0 / 3.0), channel(2.0 / 3.0))
+}
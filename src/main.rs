@@ -3,10 +3,11 @@
 //! Main driver application for the "dsp" library.
 
 // Extern crates:
-extern crate chan;            // Channels for buffering up samples in between threads
+extern crate ringbuf;         // Lock-free ring buffers for passing samples between threads
 extern crate portaudio;       // PortAudio for playing audio
 extern crate itertools_num;   // Useful vector maker for plotting
 extern crate criterion_plot;  // Criterion-plot takes samples and makes a .svg file
+extern crate hound;           // Hound for reading .wav files
 
 // Public modules:
 pub mod dsp;               // dsp-related functions